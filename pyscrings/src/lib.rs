@@ -8,7 +8,10 @@ use scrings::php::Php;
 use scrings::js::Javascript;
 use scrings::bash::Bash;
 use scrings::sql::Sql;
-use scrings::strings::Utf16le;
+use scrings::perl::Perl;
+use scrings::ruby::Ruby;
+use scrings::lua::Lua;
+use scrings::strings::{StringsIterator, Utf16le};
 use scrings::parser::{IterScrings,LanguageIterator};
 
 pub trait ReadSeek: Read + Seek + Send + Sync + 'static
@@ -22,7 +25,7 @@ impl<T: Read + Seek + Send + Sync + 'static> ReadSeek for T {}
 
 #[pyclass]
 struct PowershellItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, Utf16le, Powershell>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Powershell>
 }
 
 #[pymethods]
@@ -31,7 +34,7 @@ impl PowershellItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -49,7 +52,7 @@ fn powershell(file_like: PyObject, step: Option<usize>) -> PyResult<PowershellIt
 
 #[pyclass]
 struct PythonItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, u8, Python>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Python>
 }
 
 #[pymethods]
@@ -58,7 +61,7 @@ impl PythonItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -76,7 +79,7 @@ fn python(file_like: PyObject, step: Option<usize>) -> PyResult<PythonItemIterat
 
 #[pyclass]
 struct PhpItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, u8, Php>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Php>
 }
 
 #[pymethods]
@@ -85,7 +88,7 @@ impl PhpItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -103,7 +106,7 @@ fn php(file_like: PyObject, step: Option<usize>) -> PyResult<PhpItemIterator> {
 
 #[pyclass]
 struct JavascriptItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, u8, Javascript>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Javascript>
 }
 
 #[pymethods]
@@ -112,7 +115,7 @@ impl JavascriptItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -130,7 +133,7 @@ fn javascript(file_like: PyObject, step: Option<usize>) -> PyResult<JavascriptIt
 
 #[pyclass]
 struct BashItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, u8, Bash>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Bash>
 }
 
 #[pymethods]
@@ -139,7 +142,7 @@ impl BashItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -157,7 +160,7 @@ fn bash(file_like: PyObject, step: Option<usize>) -> PyResult<BashItemIterator>
 
 #[pyclass]
 struct SqlItemIterator {
-    iter: LanguageIterator<Box<dyn ReadSeek>, u8, Sql>
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Sql>
 }
 
 #[pymethods]
@@ -166,7 +169,7 @@ impl SqlItemIterator {
         slf
     }
     fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
-        slf.iter.next().clone()
+        slf.iter.next().map(|hit| (hit.start, hit.text))
     }
 }
 
@@ -182,6 +185,87 @@ fn sql(file_like: PyObject, step: Option<usize>) -> PyResult<SqlItemIterator> {
     }
 }
 
+#[pyclass]
+struct PerlItemIterator {
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Perl>
+}
+
+#[pymethods]
+impl PerlItemIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
+        slf.iter.next().map(|hit| (hit.start, hit.text))
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (file_like, step=None))]
+fn perl(file_like: PyObject, step: Option<usize>) -> PyResult<PerlItemIterator> {
+    match PyFileLikeObject::with_requirements(file_like, true, false, true, false) {
+        Ok(f) => {
+            let buffer = Box::new(f) as Box<dyn ReadSeek>;
+            return Ok(PerlItemIterator { iter: buffer.iter_scrings::<u8, Perl>(step.unwrap_or(20))})
+        }
+        Err(e) => return Err(e),
+    }
+}
+
+#[pyclass]
+struct RubyItemIterator {
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Ruby>
+}
+
+#[pymethods]
+impl RubyItemIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
+        slf.iter.next().map(|hit| (hit.start, hit.text))
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (file_like, step=None))]
+fn ruby(file_like: PyObject, step: Option<usize>) -> PyResult<RubyItemIterator> {
+    match PyFileLikeObject::with_requirements(file_like, true, false, true, false) {
+        Ok(f) => {
+            let buffer = Box::new(f) as Box<dyn ReadSeek>;
+            return Ok(RubyItemIterator { iter: buffer.iter_scrings::<u8, Ruby>(step.unwrap_or(20))})
+        }
+        Err(e) => return Err(e),
+    }
+}
+
+#[pyclass]
+struct LuaItemIterator {
+    iter: LanguageIterator<StringsIterator<Box<dyn ReadSeek>>, Lua>
+}
+
+#[pymethods]
+impl LuaItemIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+    fn __next__(mut slf: PyRefMut<'_, Self>) -> Option<(u64, String)> {
+        slf.iter.next().map(|hit| (hit.start, hit.text))
+    }
+}
+
+#[pyfunction]
+#[pyo3(signature = (file_like, step=None))]
+fn lua(file_like: PyObject, step: Option<usize>) -> PyResult<LuaItemIterator> {
+    match PyFileLikeObject::with_requirements(file_like, true, false, true, false) {
+        Ok(f) => {
+            let buffer = Box::new(f) as Box<dyn ReadSeek>;
+            return Ok(LuaItemIterator { iter: buffer.iter_scrings::<u8, Lua>(step.unwrap_or(20))})
+        }
+        Err(e) => return Err(e),
+    }
+}
+
 /// A Python module implemented in Rust.
 #[pymodule]
 fn pyscrings(m: &Bound<'_, PyModule>) -> PyResult<()> {
@@ -191,5 +275,8 @@ fn pyscrings(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(javascript, m)?)?;
     m.add_function(wrap_pyfunction!(bash, m)?)?;
     m.add_function(wrap_pyfunction!(sql, m)?)?;
+    m.add_function(wrap_pyfunction!(perl, m)?)?;
+    m.add_function(wrap_pyfunction!(ruby, m)?)?;
+    m.add_function(wrap_pyfunction!(lua, m)?)?;
     Ok(())
 }
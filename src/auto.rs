@@ -0,0 +1,178 @@
+use crate::bash::Bash;
+use crate::batch::Batch;
+use crate::csharp::Csharp;
+use crate::error::Result;
+use crate::html::Html;
+use crate::java::{Java, Jsp};
+use crate::js::Javascript;
+use crate::lua::Lua;
+use crate::parser::{Evidence, Parser, Region, Tolerance};
+use crate::perl::Perl;
+use crate::php::Php;
+use crate::ps::Powershell;
+use crate::python::Python;
+use crate::ruby::Ruby;
+use crate::sql::Sql;
+use crate::ts::Typescript;
+use crate::xml::Xml;
+use crate::yaml::Yaml;
+use std::cmp::Reverse;
+
+/// Result of a single language on a string
+struct Match {
+    language: &'static str,
+    regions: Vec<Region>,
+}
+
+impl Match {
+    /// Evidence of all the regions together
+    fn evidence(&self) -> Evidence {
+        let mut total = Evidence::default();
+        for evidence in self.regions.iter().filter_map(|region| region.evidence) {
+            total.matches += evidence.matches;
+            total.kinds = total.kinds.max(evidence.kinds);
+            total.covered += evidence.covered;
+            total.length = total.length.max(evidence.length);
+            total.errors += evidence.errors;
+            total.partial |= evidence.partial;
+        }
+        total
+    }
+}
+
+/// Markup holding the code of other languages,
+/// their hit only covers the embedded code but the whole string is theirs
+const CONTAINERS: &[&str] = &["jsp", "html", "xml", "yaml"];
+
+type ParseFn = fn(&str, Tolerance) -> Result<Option<Match>>;
+
+fn try_parse<P: Parser + Default>(
+    language: &'static str,
+    src: &str,
+    tolerance: Tolerance,
+) -> Result<Option<Match>> {
+    let mut parser = P::default();
+    parser.set_tolerance(tolerance);
+    let regions = parser.parse(src)?;
+    Ok((!regions.is_empty()).then_some(Match { language, regions }))
+}
+
+/// Every language tried by `Auto`, the most specific first,
+/// javascript comes before java which also accepts its `var` declarations,
+/// bash comes last as it accepts almost any sequence of words
+const LANGUAGES: &[ParseFn] = &[
+    |src, tolerance| try_parse::<Jsp>("jsp", src, tolerance),
+    |src, tolerance| try_parse::<Php>("php", src, tolerance),
+    |src, tolerance| try_parse::<Html>("html", src, tolerance),
+    |src, tolerance| try_parse::<Xml>("xml", src, tolerance),
+    |src, tolerance| try_parse::<Yaml>("yaml", src, tolerance),
+    |src, tolerance| try_parse::<Powershell>("powershell", src, tolerance),
+    |src, tolerance| try_parse::<Csharp>("csharp", src, tolerance),
+    // typescript is tried on its own, not as the fallback of javascript
+    |src, tolerance| {
+        Ok(try_parse::<Javascript>("javascript", src, tolerance)?
+            .filter(|result| result.regions.iter().all(|region| region.variant.is_none())))
+    },
+    |src, tolerance| try_parse::<Typescript>("typescript", src, tolerance),
+    |src, tolerance| try_parse::<Java>("java", src, tolerance),
+    |src, tolerance| try_parse::<Sql>("sql", src, tolerance),
+    |src, tolerance| try_parse::<Python>("python", src, tolerance),
+    |src, tolerance| try_parse::<Perl>("perl", src, tolerance),
+    |src, tolerance| try_parse::<Ruby>("ruby", src, tolerance),
+    |src, tolerance| try_parse::<Lua>("lua", src, tolerance),
+    |src, tolerance| try_parse::<Batch>("batch", src, tolerance),
+    |src, tolerance| try_parse::<Bash>("bash", src, tolerance),
+];
+
+/// Try every language on a string
+///
+/// A container wins when it finds code in its markup, otherwise the language
+/// with the best `Evidence` wins: the highest score, then the fewest errors,
+/// the most kinds and matches of discriminating nodes and the most code covered,
+/// the most specific one when several languages have the same evidence.
+/// The winner and the runners-up are reported by `Parser::languages`
+#[derive(Default)]
+pub struct Auto {
+    languages: Vec<&'static str>,
+    tolerance: Tolerance,
+}
+
+impl Parser for Auto {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let mut matches = vec![];
+        for parse in LANGUAGES {
+            // a language failing on the string doesn't stop the others
+            if let Ok(Some(result)) = parse(src, self.tolerance) {
+                matches.push(result);
+            }
+        }
+
+        // the sort is stable, so specificity breaks the ties
+        matches.sort_by_key(|result| {
+            let evidence = result.evidence();
+            (
+                !CONTAINERS.contains(&result.language),
+                Reverse(evidence.score()),
+                evidence.errors,
+                Reverse(evidence.kinds),
+                Reverse(evidence.matches),
+                Reverse(evidence.covered),
+            )
+        });
+
+        self.languages = matches.iter().map(|result| result.language).collect();
+        Ok(matches
+            .into_iter()
+            .next()
+            .map(|winner| winner.regions)
+            .unwrap_or_default())
+    }
+
+    fn languages(&self) -> Vec<&'static str> {
+        self.languages.clone()
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn winner(src: &str) -> &'static str {
+        let mut auto = Auto::default();
+        auto.parse(src).unwrap();
+        auto.languages()[0]
+    }
+
+    #[test]
+    fn javascript_function_over_typescript() {
+        assert_eq!(
+            winner("function hello(a, b) { if (a) { return b; } return a; }"),
+            "javascript"
+        );
+    }
+
+    #[test]
+    fn javascript_declaration_over_java() {
+        assert_eq!(
+            winner("var x = 1; if (x > 0) { console.log(\"positive\"); }"),
+            "javascript"
+        );
+    }
+
+    #[test]
+    fn perl_declaration_over_powershell() {
+        assert_eq!(winner("my $x = 1; if ($x) { print \"yes\"; }"), "perl");
+    }
+
+    #[test]
+    fn lua_local_over_ruby() {
+        assert_eq!(
+            winner("local x = 10\nif x > 5 then print(\"big\") end"),
+            "lua"
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use crate::error::Result;
-use crate::parser::Parser;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
 use crate::tree::Tree;
 use crate::visitor::LanguageVisitor;
 use tree_sitter_bash::language as bash_language;
 
-fn build_bash_tree(source: &str) -> Result<Tree> {
+fn build_bash_tree(source: &str) -> Result<Tree<'_>> {
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&bash_language())?;
 
@@ -13,10 +13,12 @@ fn build_bash_tree(source: &str) -> Result<Tree> {
 }
 
 #[derive(Default)]
-pub struct Bash;
+pub struct Bash {
+    tolerance: Tolerance,
+}
 
 impl Parser for Bash {
-    fn parse(&mut self, src: &str) -> Result<Option<(u64, String)>> {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
         let tree = build_bash_tree(src)?;
 
         let mut detection_rule = LanguageVisitor::new(|c| {
@@ -30,20 +32,25 @@ impl Parser for Bash {
                     | "function_definition"
                     | "compound_statement"
             )
-        });
+        })
+        .with_tolerance(self.tolerance);
 
         tree.apply(&mut detection_rule)?;
 
         if detection_rule.is_matched {
-            Ok(Some((
-                detection_rule.start.unwrap_or(0) as u64,
-                String::from(
-                    &src[detection_rule.start.unwrap_or(0)
-                        ..detection_rule.end.unwrap_or(src.len())],
-                ),
-            )))
+            let regions = tree.regions(detection_rule.spans.iter());
+            Ok(detected_regions(
+                &tree,
+                src,
+                &regions,
+                detection_rule.matches.iter(),
+            ))
         } else {
-            Ok(None)
+            Ok(vec![])
         }
     }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
 }
@@ -0,0 +1,203 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_batch::LANGUAGE as batch_language;
+
+fn build_batch_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&batch_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Batch {
+    tolerance: Tolerance,
+}
+
+impl Parser for Batch {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_batch_tree(src)?;
+        let mut detection_rule = (
+            LanguageVisitor::new(|c| {
+                matches!(c, "for_stmt" | "goto_stmt" | "setlocal_stmt" | "echo_off")
+            })
+            .with_tolerance(self.tolerance),
+            IsBatchCmd::new().with_tolerance(self.tolerance),
+        );
+        tree.apply(&mut detection_rule)?;
+
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter()),
+        );
+
+        Ok(
+            if detection_rule.0.is_matched || detection_rule.1.is_command {
+                detected_regions(
+                    &tree,
+                    src,
+                    &regions,
+                    detection_rule
+                        .0
+                        .matches
+                        .iter()
+                        .chain(detection_rule.1.matches.iter()),
+                )
+            } else {
+                vec![]
+            },
+        )
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+/// Normalize a command name as cmd.exe would resolve it
+///
+/// Carets are escape characters and are dropped,
+/// path and executable extension are ignored
+fn command_name(text: &str) -> String {
+    let name = text.replace('^', "").to_lowercase();
+    let name = name.trim_matches('"');
+    let name = name.rsplit('\\').next().unwrap_or(name);
+    name.strip_suffix(".exe")
+        .or_else(|| name.strip_suffix(".com"))
+        .unwrap_or(name)
+        .to_string()
+}
+
+pub struct IsBatchCmd {
+    is_command: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsBatchCmd {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsBatchCmd {
+    pub fn new() -> Self {
+        Self {
+            is_command: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    fn is_command(node: &Node) -> Result<bool> {
+        let mut children = node.iter();
+        let name = match children.next() {
+            Some(name) if name.kind() == "string" => command_name(name.text()?),
+            // the grammar splits the command name on carets, so use the whole first word
+            Some(name) if name.kind() == "command_name" => {
+                command_name(node.text()?.split_whitespace().next().unwrap_or_default())
+            }
+            _ => return Ok(false),
+        };
+
+        // builtins are common words, they need at least a switch to be trusted
+        let has_option = children
+            .filter(|c| c.kind() == "argument_list")
+            .flat_map(|c| c.iter())
+            .any(|c| c.kind() == "command_option");
+
+        Ok(match name.as_str() {
+            "cmd" | "powershell" | "pwsh" | "reg" | "schtasks" | "certutil" | "bitsadmin"
+            | "wmic" | "rundll32" | "regsvr32" | "mshta" | "cscript" | "wscript" | "vssadmin"
+            | "bcdedit" | "wevtutil" | "netsh" | "taskkill" | "tasklist" | "icacls" | "takeown"
+            | "xcopy" | "robocopy" | "forfiles" | "msiexec" | "whoami" | "findstr" | "sc"
+            | "setx" => true,
+            "echo" | "copy" | "del" | "erase" | "move" | "ren" | "rename" | "mkdir" | "md"
+            | "rmdir" | "rd" | "start" | "type" | "dir" | "attrib" | "net" | "exit" | "cd"
+            | "chdir" | "pushd" | "ping" | "shutdown" | "timeout" => has_option,
+            _ => false,
+        })
+    }
+
+    fn is_condition(node: &Node) -> Result<bool> {
+        if node.iter().any(|c| {
+            matches!(
+                c.kind(),
+                "if_option" | "variable_reference" | "string" | "integer"
+            )
+        }) {
+            return Ok(true);
+        }
+
+        let mut words = node.text()?.split_whitespace().skip(1);
+        Ok(match words.next().map(|w| w.to_lowercase()).as_deref() {
+            Some("not") => words.next().is_some_and(|w| {
+                matches!(
+                    w.to_lowercase().as_str(),
+                    "exist" | "defined" | "errorlevel"
+                )
+            }),
+            Some("exist") | Some("defined") | Some("errorlevel") | Some("cmdextversion") => true,
+            _ => false,
+        })
+    }
+}
+
+impl<'a> Rule<'a> for IsBatchCmd {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        let is_command = match node.kind() {
+            // matching criteria on command name
+            "cmd" => Self::is_command(node)?,
+            // an assignment needs a value, otherwise it's just a sentence starting with set
+            "variable_assignment" => node.iter().any(|c| {
+                matches!(
+                    c.kind(),
+                    "assignment_value"
+                        | "arithmetic_assignment"
+                        | "prompt_assignment"
+                        | "quoted_assignment_value"
+                        | "caret_quoted_assignment_value"
+                )
+            }),
+            // a condition on a sentence starting with if is not enough
+            "if_stmt" => Self::is_condition(node)?,
+            // substring %var:~x,y% and substitution %var:a=b% are common obfuscation
+            "variable_reference" => match node.text()?.trim_matches('%').split_once(':') {
+                Some((name, expansion)) => {
+                    !name.is_empty()
+                        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        && (expansion.starts_with('~') || expansion.contains('='))
+                }
+                None => false,
+            },
+            _ => false,
+        };
+
+        if is_command && self.tolerance.accepts_node(node) {
+            self.spans.push(node.start_abs(), node.end_abs());
+            self.is_command = true;
+            self.matches.push(node, node.has_error());
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
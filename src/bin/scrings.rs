@@ -1,17 +1,158 @@
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches};
+use scrings::auto::Auto;
 use scrings::bash::Bash;
+use scrings::batch::Batch;
+use scrings::csharp::Csharp;
+use scrings::html::Html;
+use scrings::java::{Java, Jsp};
 use scrings::js::Javascript;
-use scrings::parser::IterScrings;
+use scrings::lua::Lua;
+use scrings::parallel::ParIterScrings;
+use scrings::parser::{Hit, LanguageIterator, Parser, Tolerance};
+use scrings::perl::Perl;
 use scrings::php::Php;
 use scrings::ps::Powershell;
 use scrings::python::Python;
+use scrings::ruby::Ruby;
 use scrings::sql::Sql;
-use scrings::strings::Utf16le;
+use scrings::strings::{
+    map_file, Charset, Encoding, FileStringsIterator, Options, StreamStringsIterator,
+};
+use scrings::ts::Typescript;
+use scrings::xml::Xml;
+use scrings::yaml::Yaml;
 use std::fs::File;
-use std::io::BufReader;
+use std::io;
 
 const APPLICATION_NAME: &str = "scrings";
 
+fn print_scrings(iter: impl Iterator<Item = Hit>, matches: &ArgMatches) {
+    let min_score = matches
+        .value_of("min-score")
+        .map(|min_score| min_score.parse::<u8>().unwrap())
+        .unwrap_or(0);
+
+    for hit in iter.filter(|hit| hit.score.unwrap_or(u8::MAX) >= min_score) {
+        if matches.is_present("offset") {
+            print!("{}\t", hit.start)
+        }
+        if matches.is_present("span") {
+            print!("{}\t{}\t", hit.start, hit.end)
+        }
+        if matches.is_present("print-encoding") {
+            print!("{}\t", hit.encoding)
+        }
+        // the language is unknown until the string is parsed
+        if matches.value_of("language") == Some("auto") {
+            print!("{}\t", hit.languages.join(","))
+        }
+        if matches.is_present("print-variant") {
+            print!("{}\t", hit.variant.as_deref().unwrap_or("-"))
+        }
+        if matches.is_present("print-partial") {
+            print!("{}\t", if hit.partial { "partial" } else { "-" })
+        }
+        if matches.is_present("print-score") {
+            match hit.score {
+                Some(score) => print!("{}\t", score),
+                None => print!("-\t"),
+            }
+        }
+        if matches.is_present("escape") {
+            println!("{}", hit.text.trim().escape_default());
+        } else {
+            println!("{}", hit.text.trim());
+        }
+    }
+}
+
+/// Parsing errors accepted inside the code
+fn tolerance(matches: &ArgMatches) -> Tolerance {
+    let max_errors = matches
+        .value_of("max-errors")
+        .expect("Max errors argument is mandatory")
+        .parse::<usize>()
+        .unwrap();
+
+    let max_error_ratio = matches
+        .value_of("max-error-ratio")
+        .expect("Max error ratio argument is mandatory")
+        .parse::<f64>()
+        .unwrap();
+
+    Tolerance::new(max_errors, max_error_ratio)
+}
+
+fn scan<P: Parser + Default>(
+    file: Option<File>,
+    options: Options,
+    threads: usize,
+    matches: &ArgMatches,
+) {
+    let tolerance = tolerance(matches);
+
+    let Some(file) = file else {
+        print_scrings(
+            LanguageIterator::<_, P>::new(StreamStringsIterator::with_options(
+                io::stdin().lock(),
+                options,
+            ))
+            .with_tolerance(tolerance),
+            matches,
+        );
+        return;
+    };
+
+    if threads != 1 {
+        if let Some(mmap) = map_file(&file).unwrap() {
+            print_scrings(
+                mmap.par_iter_scrings::<P>(options, threads)
+                    .unwrap()
+                    .with_tolerance(tolerance),
+                matches,
+            );
+            return;
+        }
+    }
+
+    print_scrings(
+        LanguageIterator::<FileStringsIterator, P>::from_file(file, options)
+            .unwrap()
+            .with_tolerance(tolerance),
+        matches,
+    );
+}
+
+fn scan_language(
+    language: &str,
+    file: Option<File>,
+    options: Options,
+    threads: usize,
+    matches: &ArgMatches,
+) {
+    match language {
+        "powershell" => scan::<Powershell>(file, options, threads, matches),
+        "bash" => scan::<Bash>(file, options, threads, matches),
+        "python" => scan::<Python>(file, options, threads, matches),
+        "sql" => scan::<Sql>(file, options, threads, matches),
+        "javascript" => scan::<Javascript>(file, options, threads, matches),
+        "php" => scan::<Php>(file, options, threads, matches),
+        "batch" => scan::<Batch>(file, options, threads, matches),
+        "perl" => scan::<Perl>(file, options, threads, matches),
+        "ruby" => scan::<Ruby>(file, options, threads, matches),
+        "lua" => scan::<Lua>(file, options, threads, matches),
+        "typescript" => scan::<Typescript>(file, options, threads, matches),
+        "csharp" => scan::<Csharp>(file, options, threads, matches),
+        "java" => scan::<Java>(file, options, threads, matches),
+        "jsp" => scan::<Jsp>(file, options, threads, matches),
+        "html" => scan::<Html>(file, options, threads, matches),
+        "xml" => scan::<Xml>(file, options, threads, matches),
+        "yaml" => scan::<Yaml>(file, options, threads, matches),
+        "auto" => scan::<Auto>(file, options, threads, matches),
+        _ => (),
+    }
+}
+
 fn main() {
     let matches = App::new(APPLICATION_NAME)
         .version("0.1.7")
@@ -21,23 +162,79 @@ fn main() {
                 .long("path")
                 .short("p")
                 .takes_value(true)
-                .help("Path to the script file"),
+                .help("Path to the script file, stdin when omitted or -"),
         )
         .arg(
             Arg::with_name("step")
                 .long("step")
                 .short("s")
                 .takes_value(true)
-                .help("Min length")
+                .help("Number of chars skipped while looking for a string")
                 .default_value("20"),
         )
+        .arg(
+            Arg::with_name("min-length")
+                .long("min-length")
+                .short("m")
+                .takes_value(true)
+                .help("Min number of chars of a string [default: step + 1]"),
+        )
+        .arg(
+            Arg::with_name("max-length")
+                .long("max-length")
+                .takes_value(true)
+                .help("Max number of chars parsed at once, longer strings are parsed in overlapping windows")
+                .default_value("1048576"),
+        )
+        .arg(
+            Arg::with_name("gap")
+                .long("gap")
+                .short("g")
+                .takes_value(true)
+                .help("Max number of bytes between two runs joined in a single string")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("overlap")
+                .long("overlap")
+                .takes_value(true)
+                .help("Number of chars shared by consecutive windows")
+                .default_value("4096"),
+        )
+        .arg(
+            Arg::with_name("encoding")
+                .long("encoding")
+                .short("e")
+                .takes_value(true)
+                .multiple(true)
+                .require_delimiter(true)
+                .possible_values(&["utf8", "utf16le", "utf16be", "utf32le"])
+                .help("Encodings of the strings, scanned in a single pass [default: utf16le for powershell, utf8 and utf16le for batch and auto, utf8 otherwise]"),
+        )
+        .arg(
+            Arg::with_name("charset")
+                .long("charset")
+                .short("c")
+                .takes_value(true)
+                .possible_values(&["ascii", "ascii-tab", "unicode"])
+                .help("Chars accepted in a string")
+                .default_value("ascii"),
+        )
+        .arg(
+            Arg::with_name("threads")
+                .long("threads")
+                .short("t")
+                .takes_value(true)
+                .help("Number of threads used to scan the file, 0 to use all cores")
+                .default_value("1"),
+        )
         .arg(
             Arg::with_name("language")
                 .long("language")
                 .short("l")
                 .takes_value(true)
-                .possible_values(&["powershell", "bash", "python", "sql", "javascript", "php"])
-                .help("Language to match"),
+                .possible_values(&["powershell", "bash", "python", "sql", "javascript", "php", "batch", "perl", "ruby", "lua", "typescript", "csharp", "java", "jsp", "html", "xml", "yaml", "auto"])
+                .help("Language to match, auto tries all of them and prints the winner followed by the runners-up. html and xml skip the VBScript and VB.NET blocks, which have no parser"),
         )
         .arg(Arg::with_name("bash").help("bash"))
         .arg(
@@ -51,15 +248,59 @@ fn main() {
                 .short("o")
                 .help("Print offset in file"),
         )
+        .arg(
+            Arg::with_name("span")
+                .long("span")
+                .conflicts_with("offset")
+                .help("Print start and end offsets in file"),
+        )
+        .arg(
+            Arg::with_name("print-encoding")
+                .long("print-encoding")
+                .help("Print encoding of the string"),
+        )
+        .arg(
+            Arg::with_name("print-variant")
+                .long("print-variant")
+                .help("Print the grammar variant which matched, as php or php-only, the html and xml container, or the yaml key path"),
+        )
+        .arg(
+            Arg::with_name("print-partial")
+                .long("print-partial")
+                .help("Print partial when parsing errors were tolerated inside the hit"),
+        )
+        .arg(
+            Arg::with_name("max-errors")
+                .long("max-errors")
+                .takes_value(true)
+                .help("Max number of parsing errors tolerated inside a discriminating node")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("max-error-ratio")
+                .long("max-error-ratio")
+                .takes_value(true)
+                .help("Max share of the bytes of a discriminating node held by parsing errors, from 0 to 1")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("print-score")
+                .long("print-score")
+                .help("Print the confidence of the detection, from 0 to 100"),
+        )
+        .arg(
+            Arg::with_name("min-score")
+                .long("min-score")
+                .takes_value(true)
+                .help("Min confidence of a detection, from 0 to 100 [default: 0]"),
+        )
         .get_matches();
 
-    let file = File::open(
-        matches
-            .value_of("path")
-            .expect("Path argument is mandatory"),
-    )
-    .unwrap();
-    let buf_reader = BufReader::new(file);
+    // read stdin when there is no path
+    let file = matches
+        .value_of("path")
+        .filter(|path| *path != "-")
+        .map(|path| File::open(path).unwrap());
 
     let language = matches
         .value_of("language")
@@ -71,79 +312,72 @@ fn main() {
         .parse::<usize>()
         .unwrap();
 
-    match language {
-        "powershell" => {
-            for (offset, str) in buf_reader.iter_scrings::<Utf16le, Powershell>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
-            }
-        }
-        "bash" => {
-            for (offset, str) in buf_reader.iter_scrings::<u8, Bash>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
-            }
-        }
-        "python" => {
-            for (offset, str) in buf_reader.iter_scrings::<u8, Python>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
-            }
-        }
-        "sql" => {
-            for (offset, str) in buf_reader.iter_scrings::<u8, Sql>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
-            }
-        }
-        "javascript" => {
-            for (offset, str) in buf_reader.iter_scrings::<u8, Javascript>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
-            }
-        }
-        "php" => {
-            for (offset, str) in buf_reader.iter_scrings::<u8, Php>(step) {
-                if matches.is_present("offset") {
-                    print!("{}\t", offset)
-                }
-                if matches.is_present("escape") {
-                    println!("{}", str.trim().escape_default());
-                } else {
-                    println!("{}", str.trim());
-                }
+    let charset = match matches
+        .value_of("charset")
+        .expect("Charset argument is mandatory")
+    {
+        "ascii-tab" => Charset::AsciiTab,
+        "unicode" => Charset::Unicode,
+        _ => Charset::Ascii,
+    };
+
+    let encodings = matches
+        .values_of("encoding")
+        .map(|values| values.collect())
+        .unwrap_or_else(|| match language {
+            "powershell" => vec!["utf16le"],
+            "batch" | "auto" => vec!["utf8", "utf16le"],
+            _ => vec!["utf8"],
+        })
+        .into_iter()
+        .map(|encoding| match encoding {
+            "utf16le" => Encoding::Utf16le,
+            "utf16be" => Encoding::Utf16be,
+            "utf32le" => Encoding::Utf32le,
+            _ => Encoding::Utf8,
+        })
+        .fold(Vec::new(), |mut encodings, encoding| {
+            if !encodings.contains(&encoding) {
+                encodings.push(encoding);
             }
-        }
-        _ => (),
-    }
+            encodings
+        });
+
+    let min_length = matches
+        .value_of("min-length")
+        .map(|min_length| min_length.parse::<usize>().unwrap())
+        .unwrap_or(step + 1);
+
+    let max_length = matches
+        .value_of("max-length")
+        .expect("Max length argument is mandatory")
+        .parse::<usize>()
+        .unwrap();
+
+    let overlap = matches
+        .value_of("overlap")
+        .expect("Overlap argument is mandatory")
+        .parse::<usize>()
+        .unwrap();
+
+    let gap = matches
+        .value_of("gap")
+        .expect("Gap argument is mandatory")
+        .parse::<usize>()
+        .unwrap();
+
+    let options = Options::new(step)
+        .with_min_length(min_length)
+        .with_max_length(max_length, overlap)
+        .with_gap(gap)
+        .with_charset(charset)
+        .with_encodings(encodings);
+
+    let threads = matches
+        .value_of("threads")
+        .expect("Threads argument is mandatory")
+        .parse::<usize>()
+        .unwrap();
+
+    scan_language(language, file, options, threads, &matches);
 }
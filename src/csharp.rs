@@ -0,0 +1,199 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_c_sharp::LANGUAGE as csharp_language;
+
+fn build_csharp_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&csharp_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Csharp {
+    tolerance: Tolerance,
+}
+
+impl Csharp {
+    fn detect(src: &str, tolerance: Tolerance) -> Result<Option<Vec<Region>>> {
+        let tree = build_csharp_tree(src)?;
+
+        let mut detection_rule = (
+            LanguageVisitor::new(|c| {
+                matches!(
+                    c,
+                    "using_directive"
+                        | "namespace_declaration"
+                        | "file_scoped_namespace_declaration"
+                        | "class_declaration"
+                        | "struct_declaration"
+                        | "interface_declaration"
+                        | "enum_declaration"
+                        | "delegate_declaration"
+                        | "method_declaration"
+                )
+            })
+            .with_tolerance(tolerance),
+            IsCsharpImport::new().with_tolerance(tolerance),
+        );
+
+        tree.apply(&mut detection_rule)?;
+
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter()),
+        );
+
+        Ok(
+            if detection_rule.0.is_matched || detection_rule.1.is_import {
+                Some(detected_regions(
+                    &tree,
+                    src,
+                    &regions,
+                    detection_rule
+                        .0
+                        .matches
+                        .iter()
+                        .chain(detection_rule.1.matches.iter()),
+                ))
+            } else {
+                None
+            },
+        )
+    }
+}
+
+impl Parser for Csharp {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        if let Some(regions) = Self::detect(src, self.tolerance)? {
+            return Ok(regions);
+        }
+
+        // C# source given to Add-Type is usually held in a powershell here-string
+        for (offset, body) in here_strings(src) {
+            if let Some(regions) = Self::detect(body, self.tolerance)? {
+                return Ok(regions
+                    .into_iter()
+                    .map(|region| Region {
+                        offset: offset as u64 + region.offset,
+                        ..region
+                    })
+                    .collect());
+            }
+        }
+
+        Ok(vec![])
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+/// Find the bodies of the powershell here-strings, @" ... "@ or @' ... '@
+///
+/// Offsets are relative to the source
+fn here_strings(src: &str) -> Vec<(usize, &str)> {
+    let mut bodies = vec![];
+    let mut cursor = 0;
+
+    while let Some(index) = src[cursor..].find('@') {
+        let open = cursor + index;
+        cursor = open + 1;
+
+        let quote = match src[cursor..].chars().next() {
+            Some(quote @ ('"' | '\'')) => quote,
+            _ => continue,
+        };
+
+        // the opening quote must end its line
+        let rest = &src[cursor + 1..];
+        let skip = if rest.starts_with("\r\n") {
+            2
+        } else if rest.starts_with('\n') {
+            1
+        } else {
+            continue;
+        };
+
+        let start = cursor + 1 + skip;
+        let close = format!("\n{}@", quote);
+        if let Some(end) = src[start..].find(&close) {
+            bodies.push((start, src[start..start + end].trim_end_matches('\r')));
+            cursor = start + end + close.len();
+        }
+    }
+
+    bodies
+}
+
+/// Match P/Invoke declarations, the usual way to reach the Win32 API
+/// from C# compiled in memory
+pub struct IsCsharpImport {
+    is_import: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsCsharpImport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsCsharpImport {
+    pub fn new() -> Self {
+        Self {
+            is_import: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl<'a> Rule<'a> for IsCsharpImport {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        if node.kind() == "attribute" {
+            if let Some(name) = node.named_child("name") {
+                if matches!(
+                    name.text()?,
+                    "DllImport" | "DllImportAttribute" | "LibraryImport" | "LibraryImportAttribute"
+                ) {
+                    // the whole extern declaration carrying the attribute
+                    let declaration = node.get_parent_of_types(vec![
+                        "method_declaration",
+                        "local_function_statement",
+                    ]);
+                    let declaration = declaration.as_ref().unwrap_or(node);
+                    if self.tolerance.accepts_node(declaration) {
+                        self.spans
+                            .push(declaration.start_abs(), declaration.end_abs());
+                        self.is_import = true;
+                        self.matches.push(node, declaration.has_error());
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use rayon::ThreadPoolBuildError;
 use std::array::TryFromSliceError;
 use std::char::DecodeUtf16Error;
 use std::io::Error as IoError;
@@ -14,6 +15,7 @@ pub enum Error {
     TryFromSliceError(TryFromSliceError),
     FromUtf8Error(FromUtf8Error),
     LanguageError(LanguageError),
+    ThreadPoolBuildError(ThreadPoolBuildError),
 }
 
 impl From<DecodeUtf16Error> for Error {
@@ -52,4 +54,10 @@ impl From<LanguageError> for Error {
     }
 }
 
+impl From<ThreadPoolBuildError> for Error {
+    fn from(e: ThreadPoolBuildError) -> Error {
+        Error::ThreadPoolBuildError(e)
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
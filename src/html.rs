@@ -0,0 +1,209 @@
+use crate::error::Result;
+use crate::js::Javascript;
+use crate::parser::{embedded_regions, Parser, Region, Tolerance};
+use crate::perl::Perl;
+use crate::python::Python;
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use tree_sitter_html::LANGUAGE as html_language;
+
+fn build_html_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&html_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+/// Script embedded in a container
+struct Script {
+    /// Offset of the code in the source
+    start: usize,
+    /// Offset of the byte following the code in the source
+    end: usize,
+    /// Value of the language or type attribute, lower case
+    language: String,
+}
+
+/// Collect the script elements and the event handler attributes
+#[derive(Default)]
+struct ScriptCollector {
+    scripts: Vec<Script>,
+    container: Option<&'static str>,
+}
+
+impl ScriptCollector {
+    /// Language declared by a script start tag, javascript by default
+    fn language(start_tag: &Node) -> Result<String> {
+        let mut language = String::from("javascript");
+        for attribute in start_tag.iter().filter(|c| c.kind() == "attribute") {
+            let (Some(name), Some(value)) = (attribute.child(0), attribute.iter().last()) else {
+                continue;
+            };
+            if matches!(name.text()?.to_lowercase().as_str(), "language" | "type") {
+                language = value.text()?.trim_matches(['"', '\'']).to_lowercase();
+            }
+        }
+        Ok(language)
+    }
+}
+
+impl<'a> Rule<'a> for ScriptCollector {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        match node.kind() {
+            "tag_name" => match node.text()?.to_lowercase().as_str() {
+                "job" | "package" => self.container = Some("wsf"),
+                "hta:application" => self.container = self.container.or(Some("hta")),
+                _ => (),
+            },
+            "script_element" => {
+                if let (Some(start_tag), Some(raw_text)) = (
+                    node.iter().find(|c| c.kind() == "start_tag"),
+                    node.iter().find(|c| c.kind() == "raw_text"),
+                ) {
+                    // windows script files wrap the code in a CDATA section
+                    let text = raw_text.text()?;
+                    let mut start = raw_text.start_abs() + text.len() - text.trim_start().len();
+                    let mut code = text.trim();
+                    if let Some(inner) = code
+                        .strip_prefix("<![CDATA[")
+                        .and_then(|code| code.strip_suffix("]]>"))
+                    {
+                        start += "<![CDATA[".len();
+                        code = inner;
+                    }
+
+                    self.scripts.push(Script {
+                        start,
+                        end: start + code.len(),
+                        language: Self::language(&start_tag)?,
+                    });
+                }
+            }
+            "attribute" => {
+                if let (Some(name), Some(value)) = (
+                    node.child(0),
+                    node.iter()
+                        .find(|c| c.kind() == "quoted_attribute_value")
+                        .and_then(|c| c.iter().find(|c| c.kind() == "attribute_value")),
+                ) {
+                    let name = name.text()?.to_lowercase();
+                    if name.len() > 2 && name.starts_with("on") {
+                        self.scripts.push(Script {
+                            start: value.start_abs(),
+                            end: value.end_abs(),
+                            language: String::from("javascript"),
+                        });
+                    }
+                }
+            }
+            _ => (),
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Scripts embedded in HTML, HTA and WSF containers
+///
+/// The content of the script elements and of the event handler attributes
+/// is given to the parser of its language, each region found in a script
+/// is a hit of its own. The variant reports the container, html, hta or wsf
+#[derive(Default)]
+pub struct Html {
+    tolerance: Tolerance,
+}
+
+impl Parser for Html {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_html_tree(src)?;
+        let mut collector = ScriptCollector::default();
+        tree.apply(&mut collector)?;
+
+        let container = collector.container.unwrap_or("html");
+        let mut regions = vec![];
+
+        for script in collector.scripts {
+            let mut parser: Box<dyn Parser> = match script.language.as_str() {
+                "javascript"
+                | "jscript"
+                | "ecmascript"
+                | "text/javascript"
+                | "text/jscript"
+                | "application/javascript"
+                | "text/ecmascript"
+                | "module" => Box::<Javascript>::default(),
+                "perlscript" => Box::<Perl>::default(),
+                "python" | "text/python" => Box::<Python>::default(),
+                // VBScript has no parser yet, skipped as documented in the --language help
+                _ => continue,
+            };
+            parser.set_tolerance(self.tolerance);
+
+            let found = parser.parse(&src[script.start..script.end])?;
+            regions.extend(
+                embedded_regions(src, found, move |index| script.start + index)
+                    .map(|region| region.with_variant(container)),
+            );
+        }
+
+        Ok(regions)
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(src: &str) -> Vec<(usize, String, Option<String>)> {
+        Html::default()
+            .parse(src)
+            .unwrap()
+            .into_iter()
+            .map(|region| (region.offset as usize, region.text, region.variant))
+            .collect()
+    }
+
+    #[test]
+    fn script_and_event_handler_offsets() {
+        let src = "<html>\n<body onload=\"var count = 0; if (count == 0) { alert('empty'); }\">\n<script type=\"text/javascript\">\n  function greet(name) { return 'hi ' + name; }\n</script>\n</body>\n</html>\n";
+        let handler = "var count = 0; if (count == 0) { alert('empty'); }";
+        let function = "function greet(name) { return 'hi ' + name; }";
+        assert_eq!(
+            regions(src),
+            vec![
+                (
+                    src.find(handler).unwrap(),
+                    handler.to_string(),
+                    Some("html".to_string())
+                ),
+                (
+                    src.find(function).unwrap(),
+                    function.to_string(),
+                    Some("html".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn wsf_cdata_offset() {
+        let src = "<job id=\"main\">\n<script language=\"JScript\">\n<![CDATA[\nvar shell = new ActiveXObject(\"WScript.Shell\");\n]]>\n</script>\n</job>\n";
+        let regions = regions(src);
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].0, src.find("var shell").unwrap());
+        assert_eq!(
+            regions[0].1.trim_end(),
+            "var shell = new ActiveXObject(\"WScript.Shell\");"
+        );
+        assert_eq!(regions[0].2, Some("wsf".to_string()));
+    }
+}
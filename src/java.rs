@@ -0,0 +1,322 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, embedded_regions, Evidence, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_java::LANGUAGE as java_language;
+
+fn build_java_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&java_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Java {
+    tolerance: Tolerance,
+}
+
+impl Parser for Java {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_java_tree(src)?;
+
+        let mut detection_rule = (
+            LanguageVisitor::new(|c| {
+                matches!(
+                    c,
+                    "package_declaration"
+                        | "import_declaration"
+                        | "class_declaration"
+                        | "interface_declaration"
+                        | "enum_declaration"
+                        | "record_declaration"
+                        | "constructor_declaration"
+                        | "if_statement"
+                        | "for_statement"
+                        | "enhanced_for_statement"
+                        | "while_statement"
+                        | "do_statement"
+                        | "try_statement"
+                        | "try_with_resources_statement"
+                        | "switch_expression"
+                )
+            })
+            .with_tolerance(self.tolerance),
+            IsJavaDeclaration::new().with_tolerance(self.tolerance),
+        );
+
+        tree.apply(&mut detection_rule)?;
+
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter()),
+        );
+
+        Ok(
+            if detection_rule.0.is_matched || detection_rule.1.is_declaration {
+                detected_regions(
+                    &tree,
+                    src,
+                    &regions,
+                    detection_rule
+                        .0
+                        .matches
+                        .iter()
+                        .chain(detection_rule.1.matches.iter()),
+                )
+            } else {
+                vec![]
+            },
+        )
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+pub struct IsJavaDeclaration {
+    is_declaration: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsJavaDeclaration {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsJavaDeclaration {
+    pub fn new() -> Self {
+        Self {
+            is_declaration: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Lower case types are not java naming conventions but plain words
+    fn is_type(node: &Node) -> Result<bool> {
+        Ok(match node.kind() {
+            "type_identifier" => {
+                let text = node.text()?;
+                text == "var" || text.starts_with(|c: char| c.is_ascii_uppercase())
+            }
+            "integral_type"
+            | "floating_point_type"
+            | "boolean_type"
+            | "generic_type"
+            | "array_type"
+            | "scoped_type_identifier"
+            | "void_type" => true,
+            _ => false,
+        })
+    }
+
+    fn verify(node: &Node) -> Result<bool> {
+        Ok(match node.kind() {
+            // "<word> <word> = <word>;" must be initialized and follow naming conventions
+            "local_variable_declaration" => {
+                node.named_child("type")
+                    .map(|t| Self::is_type(&t))
+                    .transpose()?
+                    .unwrap_or(false)
+                    && node
+                        .named_child("declarator")
+                        .is_some_and(|declarator| declarator.named_child("value").is_some())
+                    && node
+                        .iter()
+                        .last()
+                        .is_some_and(|c| c.kind() == ";" && c.end_abs() > c.start_abs())
+            }
+            // "<word> <word>();" is a method without a body
+            "method_declaration" => node.named_child("body").is_some(),
+            _ => false,
+        })
+    }
+}
+
+impl<'a> Rule<'a> for IsJavaDeclaration {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        if Self::verify(node)? && self.tolerance.accepts_node(node) {
+            self.spans.push(node.start_abs(), node.end_abs());
+            self.is_declaration = true;
+            self.matches.push(node, node.has_error());
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// A JSP scriptlet, <% %>, <%= %> or <%! %>
+struct Scriptlet<'a> {
+    /// Offset of the code in the source
+    start: usize,
+    /// Java code between the tags
+    code: &'a str,
+    /// Single expression of a <%= %> tag, not statements
+    is_expression: bool,
+}
+
+/// Split the scriptlets of a JSP page
+///
+/// Directives <%@ %> and comments <%-- --%> hold no java and are skipped
+fn scriptlets(src: &str) -> Vec<Scriptlet<'_>> {
+    let mut scriptlets = vec![];
+    let mut cursor = 0;
+
+    while let Some(index) = src[cursor..].find("<%") {
+        let start = cursor + index;
+        let tag = &src[start + 2..];
+
+        let (code_start, close) = if tag.starts_with("--") {
+            (start + 4, "--%>")
+        } else if tag.starts_with(['=', '!', '@']) {
+            (start + 3, "%>")
+        } else {
+            (start + 2, "%>")
+        };
+
+        let Some(length) = src[code_start..].find(close) else {
+            break;
+        };
+        let end = code_start + length + close.len();
+
+        if !tag.starts_with(['@', '-']) {
+            scriptlets.push(Scriptlet {
+                start: code_start,
+                code: &src[code_start..code_start + length],
+                is_expression: tag.starts_with('='),
+            });
+        }
+        cursor = end;
+    }
+
+    scriptlets
+}
+
+/// Java embedded in a JSP page
+///
+/// Each scriptlet is parsed as java on its own, the body of a <%= %> tag
+/// as an expression, each region found in a scriptlet is a hit of its own
+#[derive(Default)]
+pub struct Jsp {
+    tolerance: Tolerance,
+}
+
+impl Parser for Jsp {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let mut regions = vec![];
+
+        for scriptlet in scriptlets(src) {
+            let code = scriptlet.code.trim();
+            let mut found = if scriptlet.is_expression {
+                vec![]
+            } else {
+                let mut java = Java::default();
+                java.set_tolerance(self.tolerance);
+                java.parse(scriptlet.code)?
+            };
+
+            // the tags are already a strong hint,
+            // any statement or expression parsed without error is enough
+            let is_valid = if scriptlet.is_expression {
+                // an expression is a valid statement once terminated
+                !build_java_tree(&format!("{code};"))?.root()?.has_error()
+            } else {
+                !build_java_tree(code)?.root()?.has_error()
+            };
+            if found.is_empty() && !code.is_empty() && is_valid {
+                let offset = scriptlet.code.len() - scriptlet.code.trim_start().len();
+                found.push(Region {
+                    evidence: Some(Evidence {
+                        matches: 1,
+                        kinds: 1,
+                        ..Default::default()
+                    }),
+                    ..Region::new(offset as u64, String::from(code))
+                });
+            }
+
+            regions.extend(embedded_regions(src, found, move |index| {
+                scriptlet.start + index
+            }));
+        }
+
+        Ok(regions)
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = "<%@ page import=\"java.io.*\" %>\n<%-- int hidden = 1; --%>\n<% int count = 0; %>\n<%! private int total = 1; %>\n<p><%= request.getParameter(\"name\") %></p>\n";
+
+    #[test]
+    fn scriptlets_skip_directives_and_comments() {
+        let scriptlets = scriptlets(PAGE);
+        let found: Vec<_> = scriptlets
+            .iter()
+            .map(|scriptlet| (scriptlet.start, scriptlet.code, scriptlet.is_expression))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (PAGE.find(" int count").unwrap(), " int count = 0; ", false),
+                (
+                    PAGE.find(" private").unwrap(),
+                    " private int total = 1; ",
+                    false
+                ),
+                (
+                    PAGE.find(" request").unwrap(),
+                    " request.getParameter(\"name\") ",
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn regions_are_mapped_back_to_the_page() {
+        let regions = Jsp::default().parse(PAGE).unwrap();
+        let found: Vec<_> = regions
+            .iter()
+            .map(|region| (region.offset as usize, region.text.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (PAGE.find("int count").unwrap(), "int count = 0;"),
+                (PAGE.find("private").unwrap(), "private int total = 1;"),
+                (
+                    PAGE.find("request").unwrap(),
+                    "request.getParameter(\"name\")"
+                ),
+            ]
+        );
+    }
+}
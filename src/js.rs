@@ -1,10 +1,11 @@
 use crate::error::Result;
-use crate::parser::Parser;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
 use crate::tree::Tree;
+use crate::ts::Typescript;
 use crate::visitor::LanguageVisitor;
 use tree_sitter_javascript::LANGUAGE as javascript_language;
 
-fn build_javascript_tree(source: &str) -> Result<Tree> {
+fn build_javascript_tree(source: &str) -> Result<Tree<'_>> {
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&javascript_language.into())?;
 
@@ -12,11 +13,18 @@ fn build_javascript_tree(source: &str) -> Result<Tree> {
     Ok(Tree::new(source.as_bytes(), tree_sitter))
 }
 
+/// Javascript parser
+///
+/// Candidates rejected by the javascript grammar are retried
+/// with the TypeScript and TSX grammars before giving up,
+/// the variant "typescript" reports the regions they found
 #[derive(Default)]
-pub struct Javascript;
+pub struct Javascript {
+    tolerance: Tolerance,
+}
 
 impl Parser for Javascript {
-    fn parse(&mut self, src: &str) -> Result<Option<(u64, String)>> {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
         let tree = build_javascript_tree(src)?;
 
         let mut detection_rule = LanguageVisitor::new(|c| {
@@ -25,7 +33,10 @@ impl Parser for Javascript {
                 "function_declaration"
                     | "export_statement"
                     | "debugger_statement"
-                    | "declaration"
+                    | "class_declaration"
+                    | "generator_function_declaration"
+                    | "lexical_declaration"
+                    | "variable_declaration"
                     | "if_statement"
                     | "switch_statement"
                     | "for_statement"
@@ -35,20 +46,31 @@ impl Parser for Javascript {
                     | "try_statement"
                     | "with_statement"
             )
-        });
+        })
+        .with_tolerance(self.tolerance);
 
         tree.apply(&mut detection_rule)?;
 
         if detection_rule.is_matched {
-            Ok(Some((
-                detection_rule.start.unwrap_or(0) as u64,
-                String::from(
-                    &src[detection_rule.start.unwrap_or(0)
-                        ..detection_rule.end.unwrap_or(src.len())],
-                ),
-            )))
+            let regions = tree.regions(detection_rule.spans.iter());
+            Ok(detected_regions(
+                &tree,
+                src,
+                &regions,
+                detection_rule.matches.iter(),
+            ))
         } else {
-            Ok(None)
+            let mut typescript = Typescript::default();
+            typescript.set_tolerance(self.tolerance);
+            Ok(typescript
+                .parse(src)?
+                .into_iter()
+                .map(|region| region.with_variant("typescript"))
+                .collect())
         }
     }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
 }
@@ -1,13 +1,25 @@
 #![feature(macro_metavar_expr)]
+pub mod auto;
 pub mod bash;
+pub mod batch;
+pub mod csharp;
 mod error;
+pub mod html;
+pub mod java;
 pub mod js;
+pub mod lua;
+pub mod parallel;
 pub mod parser;
+pub mod perl;
 pub mod php;
 pub mod ps;
 pub mod python;
+pub mod ruby;
 mod rule;
 pub mod sql;
 pub mod strings;
 mod tree;
+pub mod ts;
 mod visitor;
+pub mod xml;
+pub mod yaml;
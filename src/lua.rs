@@ -0,0 +1,122 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_lua::LANGUAGE as lua_language;
+
+fn build_lua_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&lua_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Lua {
+    tolerance: Tolerance,
+}
+
+impl Parser for Lua {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_lua_tree(src)?;
+
+        let mut detection_rule = (
+            LanguageVisitor::new(|c| {
+                matches!(
+                    c,
+                    "function_declaration"
+                        | "function_definition"
+                        | "if_statement"
+                        | "for_statement"
+                        | "while_statement"
+                        | "repeat_statement"
+                )
+            })
+            .with_tolerance(self.tolerance),
+            IsLuaLocal::new().with_tolerance(self.tolerance),
+        );
+
+        tree.apply(&mut detection_rule)?;
+
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter()),
+        );
+
+        Ok(
+            if detection_rule.0.is_matched || detection_rule.1.is_local {
+                detected_regions(
+                    &tree,
+                    src,
+                    &regions,
+                    detection_rule
+                        .0
+                        .matches
+                        .iter()
+                        .chain(detection_rule.1.matches.iter()),
+                )
+            } else {
+                vec![]
+            },
+        )
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+pub struct IsLuaLocal {
+    is_local: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsLuaLocal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsLuaLocal {
+    pub fn new() -> Self {
+        Self {
+            is_local: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl<'a> Rule<'a> for IsLuaLocal {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        // "local <word>" is plain text, a local declaration must be initialized
+        if node.kind() == "variable_declaration"
+            && node.iter().any(|c| c.kind() == "assignment_statement")
+            && self.tolerance.accepts_node(node)
+        {
+            self.spans.push(node.start_abs(), node.end_abs());
+            self.is_local = true;
+            self.matches.push(node, node.has_error());
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,204 @@
+use crate::error::Result;
+use crate::parser::{Hit, LanguageIterator, Parser, Tolerance};
+use crate::strings::{Options, SliceStringsIterator};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+/// Default size of the chunk handled by a worker
+const CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Number of chunks handled in a batch by each worker
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Parallel version of `LanguageIterator`
+///
+/// The buffer is split into chunks that are scanned and parsed by a pool of workers.
+/// A chunk yields every string starting inside it, even if the string goes past its end,
+/// so a string is never cut nor reported twice.
+/// Results are yielded in offset order, exactly like a sequential scan
+pub struct ParallelLanguageIterator<B, P> {
+    buffer: B,
+    options: Options,
+    pool: ThreadPool,
+    threads: usize,
+    chunk_size: usize,
+    next_chunk: usize,
+    results: VecDeque<Hit>,
+    tolerance: Tolerance,
+    language: PhantomData<P>,
+}
+
+impl<B: AsRef<[u8]> + Sync, P> ParallelLanguageIterator<B, P> {
+    /// 0 threads lets rayon pick one per core
+    pub fn new(buffer: B, options: Options, threads: usize) -> Result<Self> {
+        let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+        Ok(Self {
+            buffer,
+            options,
+            threads: pool.current_num_threads(),
+            pool,
+            chunk_size: CHUNK_SIZE,
+            next_chunk: 0,
+            results: VecDeque::new(),
+            tolerance: Tolerance::default(),
+            language: PhantomData,
+        })
+    }
+
+    /// Parsing errors accepted by the parser
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Size of the chunk handled by a worker, at least 1 byte
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+}
+
+impl<B, P> Iterator for ParallelLanguageIterator<B, P>
+where
+    B: AsRef<[u8]> + Sync,
+    P: Parser + Default,
+{
+    type Item = Hit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.results.is_empty() {
+            let buffer = self.buffer.as_ref();
+            let chunk_size = self.chunk_size;
+            let nb_chunks = buffer.len().div_ceil(chunk_size);
+            if self.next_chunk >= nb_chunks {
+                return None;
+            }
+
+            let chunks =
+                self.next_chunk..nb_chunks.min(self.next_chunk + self.threads * CHUNKS_PER_THREAD);
+            self.next_chunk = chunks.end;
+
+            let options = &self.options;
+            let tolerance = self.tolerance;
+            let batch: Vec<Vec<Hit>> = self.pool.install(|| {
+                chunks
+                    .into_par_iter()
+                    .map(|chunk| {
+                        LanguageIterator::<_, P>::new(SliceStringsIterator::with_range(
+                            buffer,
+                            options.clone(),
+                            (chunk * chunk_size) as u64,
+                            ((chunk + 1) * chunk_size) as u64,
+                        ))
+                        .with_tolerance(tolerance)
+                        .collect()
+                    })
+                    .collect()
+            });
+
+            self.results.extend(batch.into_iter().flatten());
+        }
+        self.results.pop_front()
+    }
+}
+
+pub trait ParIterScrings {
+    fn par_iter_scrings<P: Parser>(
+        self,
+        options: Options,
+        threads: usize,
+    ) -> Result<ParallelLanguageIterator<Self, P>>
+    where
+        Self: AsRef<[u8]> + Sync + Sized;
+}
+
+impl<T: AsRef<[u8]> + Sync> ParIterScrings for T {
+    fn par_iter_scrings<P: Parser>(
+        self,
+        options: Options,
+        threads: usize,
+    ) -> Result<ParallelLanguageIterator<Self, P>> {
+        ParallelLanguageIterator::new(self, options, threads)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bash::Bash;
+    use crate::strings::Encoding;
+
+    fn sequential(buffer: &[u8], options: Options) -> Vec<Hit> {
+        LanguageIterator::<_, Bash>::new(SliceStringsIterator::with_options(buffer, options))
+            .collect()
+    }
+
+    fn parallel(buffer: &[u8], options: Options, chunk_size: usize) -> Vec<Hit> {
+        ParallelLanguageIterator::<_, Bash>::new(buffer, options, 2)
+            .unwrap()
+            .with_chunk_size(chunk_size)
+            .collect()
+    }
+
+    /// Commands separated by junk, some split by a gap and some longer than a window
+    fn commands(encoding: Encoding) -> Vec<u8> {
+        let encode = |s: &str| match encoding {
+            Encoding::Utf16le => s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            _ => s.as_bytes().to_vec(),
+        };
+
+        let mut buffer = vec![];
+        for i in 0..40 {
+            buffer.extend(encode("\0\0\0"));
+            buffer.extend(encode(&format!(
+                "if [ -d /tmp/dir{i} ]; then ls; fi;\x01while read l; do echo $l; done"
+            )));
+            if i % 10 == 0 {
+                let long: String = (0..10)
+                    .map(|j| format!("if [ -f /tmp/f{j} ]; then rm /tmp/f{j}; fi; "))
+                    .collect();
+                buffer.extend(encode("\0\0\0"));
+                buffer.extend(encode(&long));
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn chunks_yield_the_same_hits() {
+        let narrow = commands(Encoding::Utf8);
+        let wide = commands(Encoding::Utf16le);
+        let both = [narrow.as_slice(), wide.as_slice()].concat();
+
+        for (buffer, options) in [
+            (
+                &both,
+                Options::new(4)
+                    .with_max_length(64, 16)
+                    .with_encodings(vec![Encoding::Utf8, Encoding::Utf16le]),
+            ),
+            (&narrow, Options::new(4).with_max_length(64, 16).with_gap(1)),
+            (
+                &wide,
+                Options::new(4)
+                    .with_max_length(64, 16)
+                    .with_gap(2)
+                    .with_encodings(vec![Encoding::Utf16le]),
+            ),
+        ] {
+            let expected = sequential(buffer, options.clone());
+            for encoding in &options.encodings {
+                assert!(expected.iter().any(|hit| hit.encoding == *encoding));
+            }
+            for chunk_size in [17, 64, 100, 1000] {
+                assert_eq!(
+                    parallel(buffer, options.clone(), chunk_size),
+                    expected,
+                    "{options:?}, chunks of {chunk_size} bytes"
+                );
+            }
+        }
+    }
+}
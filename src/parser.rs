@@ -1,65 +1,497 @@
 use crate::error::Result;
-use crate::strings::{Decode, StringsIterator};
+use crate::strings::{
+    Candidate, Decode, Encoding, FileStringsIterator, Options, SliceStringsIterator,
+    StreamStringsIterator, StringsIterator,
+};
+use crate::tree::{Node, Tree};
+use std::collections::VecDeque;
+use std::fs::File;
 use std::io::{Read, Seek};
 use std::marker::PhantomData;
 
 pub trait Parser {
-    fn parse(&mut self, src: &str) -> Result<Option<(u64, String)>>;
+    /// Regions of the string holding code,
+    /// they are disjoint and sorted, see `Tree::regions`
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>>;
+
+    /// Languages which accepted the last parsed string, the winner first,
+    /// for parsers trying several languages
+    fn languages(&self) -> Vec<&'static str> {
+        vec![]
+    }
+
+    /// Parsing errors accepted inside the code, strict by default
+    fn set_tolerance(&mut self, _tolerance: Tolerance) {}
+}
+
+/// Parsing errors accepted inside a discriminating node
+///
+/// By default a single ERROR or MISSING node discards the nodes around it,
+/// a tolerance keeps them when the errors are few and small enough
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Max number of ERROR and MISSING nodes
+    pub max_errors: usize,
+    /// Max share of the bytes of the node held by ERROR nodes, from 0 to 1
+    pub max_error_ratio: f64,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self::new(0, 1.0)
+    }
+}
+
+impl Tolerance {
+    pub fn new(max_errors: usize, max_error_ratio: f64) -> Self {
+        Self {
+            max_errors,
+            max_error_ratio,
+        }
+    }
+
+    /// Errors found in a node of `length` bytes are accepted
+    pub fn accepts(&self, errors: usize, error_bytes: usize, length: usize) -> bool {
+        errors <= self.max_errors && error_bytes as f64 <= self.max_error_ratio * length as f64
+    }
+
+    /// Errors found inside a node are accepted
+    pub fn accepts_node(&self, node: &Node) -> bool {
+        let (errors, error_bytes) = node.errors();
+        self.accepts(errors, error_bytes, node.end_abs() - node.start_abs())
+    }
+}
+
+/// What a parser has seen on the string it accepted
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Evidence {
+    /// Number of discriminating nodes matched
+    pub matches: usize,
+    /// Number of distinct kinds of discriminating nodes matched
+    pub kinds: usize,
+    /// Number of bytes of the hits
+    pub covered: usize,
+    /// Number of bytes of the parsed string
+    pub length: usize,
+    /// Number of ERROR and MISSING nodes inside the hits
+    pub errors: usize,
+    /// Some nodes were kept despite parsing errors, see `Tolerance`
+    pub partial: bool,
+}
+
+impl Evidence {
+    /// Evidence gathered inside a region of a string of `length` bytes,
+    /// from the matched nodes sorted by start, see `Matches`
+    fn in_region(
+        matches: &[(usize, &str, bool)],
+        region: (usize, usize),
+        length: usize,
+        errors: usize,
+    ) -> Self {
+        let first = matches.partition_point(|(start, _, _)| *start < region.0);
+        let mut count = 0;
+        let mut kinds = vec![];
+        let mut partial = false;
+        for (_, kind, tolerated) in matches[first..]
+            .iter()
+            .take_while(|(start, _, _)| *start < region.1)
+        {
+            count += 1;
+            if !kinds.contains(kind) {
+                kinds.push(*kind);
+            }
+            partial |= tolerated;
+        }
+
+        Self {
+            matches: count,
+            kinds: kinds.len(),
+            covered: region.1 - region.0,
+            length,
+            errors,
+            partial,
+        }
+    }
+
+    /// Confidence of the detection, from 0 to 100
+    ///
+    /// More discriminating nodes, of more kinds, covering more of the string
+    /// give a higher score, parsing errors inside the hit lower it
+    pub fn score(&self) -> u8 {
+        if self.matches == 0 || self.length == 0 {
+            return 0;
+        }
+
+        // 1 match is worth 0.5, 2 are worth 0.75, and so on
+        let strength = 1.0 - 0.5f64.powi(self.matches.min(32) as i32);
+        let variety = 1.0 - 0.5f64.powi(self.kinds.min(32) as i32);
+        let coverage = (self.covered as f64 / self.length as f64).min(1.0);
+        let quality = 1.0 / (1.0 + self.errors as f64);
+
+        ((0.4 * strength + 0.3 * variety + 0.3 * coverage) * quality * 100.0).round() as u8
+    }
+}
+
+/// Code found in a parsed string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// Offset of the region in the parsed string
+    pub offset: u64,
+    /// Code of the region
+    pub text: String,
+    /// Grammar variant which matched, for languages parsed with several grammars,
+    /// or where the code was found in its container
+    pub variant: Option<String>,
+    /// Evidence gathered inside the region, see `Evidence::score`
+    pub evidence: Option<Evidence>,
+}
+
+impl Region {
+    pub fn new(offset: u64, text: String) -> Self {
+        Self {
+            offset,
+            text,
+            variant: None,
+            evidence: None,
+        }
+    }
+
+    pub fn with_variant(mut self, variant: impl Into<String>) -> Self {
+        self.variant = Some(variant.into());
+        self
+    }
+}
+
+/// Regions of the parsed string with the evidence gathered inside each of them,
+/// from the nodes matched by the rules, see `Matches`
+pub(crate) fn detected_regions(
+    tree: &Tree,
+    src: &str,
+    regions: &[(usize, usize)],
+    matches: impl IntoIterator<Item = (usize, &'static str, bool)>,
+) -> Vec<Region> {
+    let mut matches: Vec<(usize, &str, bool)> = matches.into_iter().collect();
+    matches.sort_unstable();
+
+    regions
+        .iter()
+        .zip(tree.error_counts(regions))
+        .map(|(&(start, end), errors)| Region {
+            evidence: Some(Evidence::in_region(
+                &matches,
+                (start, end),
+                src.len(),
+                errors,
+            )),
+            ..Region::new(start as u64, String::from(&src[start..end]))
+        })
+        .collect()
+}
+
+/// Move the regions found in code embedded in a container to the container,
+/// `source_offset` maps an index in the code to an offset in `src`
+///
+/// Each region is a hit of its own, its coverage is measured on the container
+pub(crate) fn embedded_regions<'a>(
+    src: &'a str,
+    regions: Vec<Region>,
+    source_offset: impl Fn(usize) -> usize + 'a,
+) -> impl Iterator<Item = Region> + 'a {
+    regions
+        .into_iter()
+        .filter(|region| !region.text.is_empty())
+        .map(move |region| {
+            let offset = region.offset as usize;
+            let start = source_offset(offset);
+            // from the last byte, the chars removed from the code may follow it
+            let end = source_offset(offset + region.text.len() - 1) + 1;
+            Region {
+                offset: start as u64,
+                text: String::from(&src[start..end]),
+                variant: region.variant,
+                evidence: region.evidence.map(|evidence| Evidence {
+                    covered: end - start,
+                    length: src.len(),
+                    ..evidence
+                }),
+            }
+        })
 }
 
 pub trait Parsable {
-    fn is<P: Parser + Default>(&self) -> Result<Option<(u64, String)>>;
+    fn is<P: Parser + Default>(&self) -> Result<Vec<Region>>;
 }
 
 impl Parsable for String {
-    fn is<P: Parser + Default>(&self) -> Result<Option<(u64, String)>> {
+    fn is<P: Parser + Default>(&self) -> Result<Vec<Region>> {
         P::default().parse(self.as_str())
     }
 }
 
-pub struct LanguageIterator<T: Read + Seek, U, P> {
-    strings_iterator: StringsIterator<T, U>,
+/// A semantic string found in the input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hit {
+    /// Offset of the first byte of the hit in the input
+    pub start: u64,
+    /// Offset of the byte following the hit in the input
+    pub end: u64,
+    /// Decoded content of the hit
+    pub text: String,
+    /// Encoding of the hit in the input
+    pub encoding: Encoding,
+    /// Grammar variant which matched, see `Region::variant`
+    pub variant: Option<String>,
+    /// Winner and runners-up, see `Parser::languages`
+    pub languages: Vec<&'static str>,
+    /// Confidence of the detection from 0 to 100, see `Evidence::score`
+    pub score: Option<u8>,
+    /// Parsing errors were tolerated inside the hit, see `Tolerance`
+    pub partial: bool,
+}
+
+impl Hit {
+    /// Merge a hit found in the next window of the same string
+    ///
+    /// The chars between both hits are read from the window
+    fn stitch(&mut self, other: Hit, window: &Candidate) {
+        let end = self.end.max(other.end);
+        if other.start < self.start {
+            // self is also in the window
+            self.text =
+                String::from(&window.text[window.text_index(other.start)..window.text_index(end)]);
+            self.start = other.start;
+        } else if end > self.end {
+            self.text
+                .push_str(&window.text[window.text_index(self.end)..window.text_index(end)]);
+        }
+        self.end = end;
+        self.score = self.score.max(other.score);
+        self.partial |= other.partial;
+    }
+}
+
+/// Run a language parser over each string
+/// produced by a strings iterator
+///
+/// Each region of a string is a hit of its own,
+/// hits found in consecutive windows of a long string are stitched together
+pub struct LanguageIterator<I, P> {
+    strings_iterator: I,
+    /// Hits of the last window not returned yet
+    ready: VecDeque<Hit>,
+    /// Last hit, kept while it may go on in the next window
+    pending: Option<Hit>,
+    /// End of the last window when it was truncated
+    edge: Option<u64>,
+    tolerance: Tolerance,
     language: PhantomData<P>,
 }
 
-impl<T: Read + Seek, U, P> LanguageIterator<T, U, P> {
-    pub fn new(buffer: T, step: usize) -> Self {
+impl<I, P> LanguageIterator<I, P> {
+    pub fn new(strings_iterator: I) -> Self {
         Self {
-            strings_iterator: StringsIterator::new(buffer, step),
+            strings_iterator,
+            ready: VecDeque::new(),
+            pending: None,
+            edge: None,
+            tolerance: Tolerance::default(),
             language: PhantomData,
         }
     }
+
+    /// Parsing errors accepted by the parser
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl<P> LanguageIterator<FileStringsIterator, P> {
+    /// Memory map the file when it's a regular file
+    /// and read it through a buffer otherwise
+    pub fn from_file(file: File, options: Options) -> Result<Self> {
+        Ok(Self::new(FileStringsIterator::new(file, options)?))
+    }
 }
 
-impl<T: Read + Seek, U: Decode + Into<u64> + Copy, P: Parser + Default> Iterator
-    for LanguageIterator<T, U, P>
+impl<I, P> Iterator for LanguageIterator<I, P>
+where
+    I: Iterator<Item = Candidate>,
+    P: Parser + Default,
 {
-    type Item = (u64, String);
+    type Item = Hit;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while let Some((position, s)) = self.strings_iterator.next() {
-            if let Some((offset, result)) = s.is::<P>().unwrap_or(None) {
-                return Some((position + offset, result));
+        loop {
+            if let Some(hit) = self.ready.pop_front() {
+                return Some(hit);
+            }
+
+            // the string of the pending hit is over
+            if self.edge.is_none() {
+                if let Some(hit) = self.pending.take() {
+                    return Some(hit);
+                }
+            }
+
+            let Some(candidate) = self.strings_iterator.next() else {
+                return self.pending.take();
+            };
+            let edge = std::mem::replace(
+                &mut self.edge,
+                candidate
+                    .truncated
+                    .then(|| candidate.source_end(candidate.text.len())),
+            );
+
+            let mut parser = P::default();
+            parser.set_tolerance(self.tolerance);
+            let regions = parser.parse(&candidate.text).unwrap_or_default();
+            // offsets are indexes in the decoded string, not in the input
+            let source_ranges = candidate.source_ranges(
+                &regions
+                    .iter()
+                    .map(|region| {
+                        let offset = region.offset as usize;
+                        (offset, offset + region.text.len())
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            for (region, (start, end)) in regions.into_iter().zip(source_ranges) {
+                let hit = Hit {
+                    start,
+                    end,
+                    text: region.text,
+                    encoding: candidate.encoding,
+                    variant: region.variant,
+                    languages: parser.languages(),
+                    score: region.evidence.map(|evidence| evidence.score()),
+                    partial: region.evidence.is_some_and(|evidence| evidence.partial),
+                };
+
+                // both hits reach the overlap of the windows
+                match (self.pending.as_mut(), edge) {
+                    (Some(pending), Some(edge))
+                        if candidate.continued
+                            && pending.end >= candidate.offset
+                            && hit.start <= edge =>
+                    {
+                        if hit.end < pending.start {
+                            // already returned with the previous window
+                        } else if hit.start > pending.end {
+                            self.ready.extend(self.pending.replace(hit));
+                        } else {
+                            pending.stitch(hit, &candidate)
+                        }
+                    }
+                    _ => self.ready.extend(self.pending.replace(hit)),
+                }
             }
         }
-        None
     }
 }
 
 pub trait IterScrings {
-    fn iter_scrings<U: Decode + Into<u64> + Copy, P: Parser>(
+    /// Either a step, which is also the min length of a string,
+    /// or `Options` to set the step and the min length independently
+    fn iter_scrings<U: Decode, P: Parser>(
         self,
-        step: usize,
-    ) -> LanguageIterator<Self, U, P>
+        options: impl Into<Options>,
+    ) -> LanguageIterator<StringsIterator<Self>, P>
     where
         Self: Read + Seek + Sized;
 }
 
 impl<T: Read + Seek> IterScrings for T {
-    fn iter_scrings<U: Decode + Into<u64> + Copy, P: Parser>(
+    fn iter_scrings<U: Decode, P: Parser>(
+        self,
+        options: impl Into<Options>,
+    ) -> LanguageIterator<StringsIterator<Self>, P> {
+        LanguageIterator::new(StringsIterator::with_options(
+            self,
+            options.into().with_encodings(vec![U::ENCODING]),
+        ))
+    }
+}
+
+pub trait IterSliceScrings {
+    /// Either a step, which is also the min length of a string,
+    /// or `Options` to set the step and the min length independently
+    fn iter_slice_scrings<U: Decode, P: Parser>(
+        self,
+        options: impl Into<Options>,
+    ) -> LanguageIterator<SliceStringsIterator<Self>, P>
+    where
+        Self: AsRef<[u8]> + Sized;
+}
+
+impl<T: AsRef<[u8]>> IterSliceScrings for T {
+    fn iter_slice_scrings<U: Decode, P: Parser>(
+        self,
+        options: impl Into<Options>,
+    ) -> LanguageIterator<SliceStringsIterator<Self>, P> {
+        LanguageIterator::new(SliceStringsIterator::with_options(
+            self,
+            options.into().with_encodings(vec![U::ENCODING]),
+        ))
+    }
+}
+
+pub trait IterStreamScrings {
+    /// Scan a stream that can't seek, reading it once
+    fn iter_stream_scrings<U: Decode, P: Parser>(
+        self,
+        options: impl Into<Options>,
+    ) -> LanguageIterator<StreamStringsIterator<Self>, P>
+    where
+        Self: Read + Sized;
+}
+
+impl<T: Read> IterStreamScrings for T {
+    fn iter_stream_scrings<U: Decode, P: Parser>(
         self,
-        step: usize,
-    ) -> LanguageIterator<Self, U, P> {
-        LanguageIterator::new(self, step)
+        options: impl Into<Options>,
+    ) -> LanguageIterator<StreamStringsIterator<Self>, P> {
+        LanguageIterator::new(StreamStringsIterator::with_options(
+            self,
+            options.into().with_encodings(vec![U::ENCODING]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::python::Python;
+
+    #[test]
+    fn each_region_is_a_hit() {
+        let src = b"\0x = 1\nif x:\n    pass\n)))\nimport os\n\0";
+        let hits: Vec<Hit> = src.iter_slice_scrings::<u8, Python>(4).collect();
+
+        let found: Vec<(u64, u64, &str)> = hits
+            .iter()
+            .map(|hit| (hit.start, hit.end, hit.text.as_str()))
+            .collect();
+        assert_eq!(found, [(7, 21, "if x:\n    pass"), (26, 35, "import os")]);
+        // each region is scored on its own nodes
+        assert!(hits[0].score > hits[1].score);
+        assert!(hits.iter().all(|hit| !hit.partial));
+    }
+
+    #[test]
+    fn partial_only_when_errors_are_tolerated() {
+        let src = b"\0x = requests.get(\"http://a\" +++ ))) , 1)\0";
+
+        // the call holds errors, it is not a match of a strict parser
+        assert_eq!(src.iter_slice_scrings::<u8, Python>(4).count(), 0);
+
+        let hits: Vec<Hit> = src
+            .iter_slice_scrings::<u8, Python>(4)
+            .with_tolerance(Tolerance::new(5, 1.0))
+            .collect();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].text, "requests.get(\"http://a\" +++ ))) , 1)");
+        assert!(hits[0].partial);
     }
 }
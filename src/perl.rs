@@ -0,0 +1,131 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_perl::LANGUAGE as perl_language;
+
+fn build_perl_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&perl_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Perl {
+    tolerance: Tolerance,
+}
+
+impl Parser for Perl {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_perl_tree(src)?;
+
+        let mut detection_rule = (
+            LanguageVisitor::new(|c| {
+                matches!(
+                    c,
+                    "use_parent_statement"
+                        | "use_constant_statement"
+                        | "require_statement"
+                        | "package_statement"
+                        | "function_definition"
+                        | "anonymous_function"
+                        | "variable_declaration"
+                        | "if_statement"
+                        | "unless_statement"
+                        | "while_statement"
+                        | "until_statement"
+                        | "for_statement_1"
+                        | "for_statement_2"
+                )
+            })
+            .with_tolerance(self.tolerance),
+            IsPerlUse::new().with_tolerance(self.tolerance),
+        );
+
+        tree.apply(&mut detection_rule)?;
+
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter()),
+        );
+
+        Ok(if detection_rule.0.is_matched || detection_rule.1.is_use {
+            detected_regions(
+                &tree,
+                src,
+                &regions,
+                detection_rule
+                    .0
+                    .matches
+                    .iter()
+                    .chain(detection_rule.1.matches.iter()),
+            )
+        } else {
+            vec![]
+        })
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+pub struct IsPerlUse {
+    is_use: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsPerlUse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsPerlUse {
+    pub fn new() -> Self {
+        Self {
+            is_use: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl<'a> Rule<'a> for IsPerlUse {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        // "no <word>" is too common in plain text, only keep complete "use" statements
+        if node.kind() == "use_no_statement"
+            && node.child(0).map(|c| c.kind()) == Some("use")
+            && node
+                .iter()
+                .last()
+                .is_some_and(|c| c.kind() == ";" && c.end_abs() > c.start_abs())
+            && self.tolerance.accepts_node(node)
+        {
+            self.spans.push(node.start_abs(), node.end_abs());
+            self.is_use = true;
+            self.matches.push(node, node.has_error());
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
@@ -1,10 +1,11 @@
 use crate::error::Result;
-use crate::parser::Parser;
-use crate::tree::Tree;
-use crate::visitor::LanguageVisitor;
-use tree_sitter_php::LANGUAGE_PHP as php_language;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
+use tree_sitter_php::{LANGUAGE_PHP as php_language, LANGUAGE_PHP_ONLY as php_only_language};
 
-fn build_php_tree(source: &str) -> Result<Tree> {
+fn build_php_tree(source: &str) -> Result<Tree<'_>> {
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&php_language.into())?;
 
@@ -12,14 +13,68 @@ fn build_php_tree(source: &str) -> Result<Tree> {
     Ok(Tree::new(source.as_bytes(), tree_sitter))
 }
 
-#[derive(Default)]
-pub struct Php;
+/// Parse php code without the opening tag,
+/// as given to eval or stored in a database
+fn build_php_only_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&php_only_language.into())?;
 
-impl Parser for Php {
-    fn parse(&mut self, src: &str) -> Result<Option<(u64, String)>> {
-        let tree = build_php_tree(src)?;
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+fn detect(tree: &Tree, src: &str, tolerance: Tolerance) -> Result<Option<Vec<Region>>> {
+    let mut detection_rule = LanguageVisitor::new(|c| {
+        matches!(
+            c,
+            "if_statement"
+                | "switch_statement"
+                | "while_statement"
+                | "do_statement"
+                | "for_statement"
+                | "try_statement"
+                | "declare_statement"
+                | "echo_statement"
+                | "unset_statement"
+                | "const_declaration"
+                | "function_definition"
+                | "class_declaration"
+                | "interface_declaration"
+                | "trait_declaration"
+                | "enum_declaration"
+                | "namespace_definition"
+                | "namespace_use_declaration"
+                | "global_declaration"
+                | "function_static_declaration"
+                | "assignment_expression"
+                | "require_expression"
+                | "require_once_expression"
+                | "match_expression"
+        )
+    })
+    .with_tolerance(tolerance);
+
+    tree.apply(&mut detection_rule)?;
+
+    if detection_rule.is_matched {
+        let regions = tree.regions(detection_rule.spans.iter());
+        Ok(Some(detected_regions(
+            tree,
+            src,
+            &regions,
+            detection_rule.matches.iter(),
+        )))
+    } else {
+        Ok(None)
+    }
+}
 
-        let mut detection_rule = LanguageVisitor::new(|c| {
+/// Without the opening tag, statements common to other languages
+/// such as echo or assignments are not enough,
+/// and the code must use at least one php variable
+fn detect_php_only(tree: &Tree, src: &str, tolerance: Tolerance) -> Result<Option<Vec<Region>>> {
+    let mut detection_rule = (
+        LanguageVisitor::new(|c| {
             matches!(
                 c,
                 "if_statement"
@@ -27,39 +82,154 @@ impl Parser for Php {
                     | "while_statement"
                     | "do_statement"
                     | "for_statement"
+                    | "foreach_statement"
                     | "try_statement"
-                    | "declare_statement"
-                    | "echo_statement"
-                    | "unset_statement"
-                    | "const_declaration"
                     | "function_definition"
                     | "class_declaration"
                     | "interface_declaration"
                     | "trait_declaration"
                     | "enum_declaration"
-                    | "namespace_definition"
-                    | "namespace_use_declaration"
                     | "global_declaration"
-                    | "function_static_declaration"
-                    | "assignment_expression"
-                    | "require_expression"
-                    | "require_once_expression"
                     | "match_expression"
             )
-        });
-
-        tree.apply(&mut detection_rule)?;
-
-        if detection_rule.is_matched {
-            Ok(Some((
-                detection_rule.start.unwrap_or(0) as u64,
-                String::from(
-                    &src[detection_rule.start.unwrap_or(0)
-                        ..detection_rule.end.unwrap_or(src.len())],
-                ),
-            )))
+        })
+        .with_tolerance(tolerance),
+        IsPhpCall::new().with_tolerance(tolerance),
+    );
+
+    tree.apply(&mut detection_rule)?;
+
+    // every region must use a php variable
+    let mut regions = tree.regions(
+        detection_rule
+            .0
+            .spans
+            .iter()
+            .chain(detection_rule.1.spans.iter()),
+    );
+    regions.retain(|(start, end)| src[*start..*end].contains('$'));
+
+    Ok(
+        if (detection_rule.0.is_matched || detection_rule.1.is_call) && !regions.is_empty() {
+            Some(detected_regions(
+                tree,
+                src,
+                &regions,
+                detection_rule
+                    .0
+                    .matches
+                    .iter()
+                    .chain(detection_rule.1.matches.iter()),
+            ))
         } else {
-            Ok(None)
+            None
+        },
+    )
+}
+
+/// PHP parser
+///
+/// Code inside `<?php` tags is tried first, then bare code.
+/// The variant reports which form matched, "php" or "php-only"
+#[derive(Default)]
+pub struct Php {
+    tolerance: Tolerance,
+}
+
+impl Parser for Php {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let (regions, variant) = match detect(&build_php_tree(src)?, src, self.tolerance)? {
+            Some(regions) => (regions, "php"),
+            None => (
+                detect_php_only(&build_php_only_tree(src)?, src, self.tolerance)?
+                    .unwrap_or_default(),
+                "php-only",
+            ),
+        };
+
+        Ok(regions
+            .into_iter()
+            .map(|region| region.with_variant(variant))
+            .collect())
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+/// Match calls to the functions webshells are made of,
+/// with a php variable in the arguments
+pub struct IsPhpCall {
+    is_call: bool,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsPhpCall {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IsPhpCall {
+    pub fn new() -> Self {
+        Self {
+            is_call: false,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
+        }
+    }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+impl<'a> Rule<'a> for IsPhpCall {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        if node.kind() == "function_call_expression" && self.tolerance.accepts_node(node) {
+            if let (Some(function), Some(arguments)) =
+                (node.named_child("function"), node.named_child("arguments"))
+            {
+                if matches!(
+                    function.text()?.to_lowercase().as_str(),
+                    "eval"
+                        | "assert"
+                        | "system"
+                        | "exec"
+                        | "shell_exec"
+                        | "passthru"
+                        | "popen"
+                        | "proc_open"
+                        | "pcntl_exec"
+                        | "base64_decode"
+                        | "gzinflate"
+                        | "gzuncompress"
+                        | "str_rot13"
+                        | "create_function"
+                        | "call_user_func"
+                        | "call_user_func_array"
+                        | "preg_replace"
+                        | "file_put_contents"
+                        | "move_uploaded_file"
+                ) && arguments.text()?.contains('$')
+                {
+                    self.spans.push(node.start_abs(), node.end_abs());
+                    self.is_call = true;
+                    self.matches.push(node, node.has_error());
+                }
+            }
         }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
     }
 }
@@ -1,12 +1,11 @@
 use crate::error::Result;
-use crate::parser::Parser;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
 use crate::rule::Rule;
 use crate::tree::{Node, Tree};
-use crate::visitor::LanguageVisitor;
-use std::cmp::{max, min};
+use crate::visitor::{LanguageVisitor, Matches, Spans};
 use tree_sitter_python::language as python_language;
 
-fn build_python_tree(source: &str) -> crate::error::Result<Tree> {
+fn build_python_tree(source: &str) -> crate::error::Result<Tree<'_>> {
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&python_language()).unwrap();
 
@@ -15,10 +14,12 @@ fn build_python_tree(source: &str) -> crate::error::Result<Tree> {
 }
 
 #[derive(Default)]
-pub struct Python;
+pub struct Python {
+    tolerance: Tolerance,
+}
 
 impl Parser for Python {
-    fn parse(&mut self, src: &str) -> crate::error::Result<Option<(u64, String)>> {
+    fn parse(&mut self, src: &str) -> crate::error::Result<Vec<Region>> {
         let tree = build_python_tree(src)?;
 
         let mut detection_rule = (
@@ -42,87 +43,92 @@ impl Parser for Python {
                         | "exec_statement"
                         | "import_statement"
                 )
-            }),
-            IsPythonSubscript::new(),
-            IsPythonFunction::new(),
+            })
+            .with_tolerance(self.tolerance),
+            IsPythonSubscript::new().with_tolerance(self.tolerance),
+            IsPythonFunction::new().with_tolerance(self.tolerance),
         );
 
         tree.apply(&mut detection_rule)?;
 
-        let start = match (
-            detection_rule.0.start,
-            detection_rule.1.start,
-            detection_rule.2.start,
-        ) {
-            (None, None, None) => None,
-            (None, Some(x), None) | (Some(x), None, None) | (None, None, Some(x)) => Some(x),
-            (Some(x), Some(y), None) | (Some(x), None, Some(y)) | (None, Some(x), Some(y)) => {
-                Some(min(x, y))
-            }
-            (Some(x), Some(y), Some(z)) => Some(min(min(x, y), z)),
-        };
-
-        let end = match (
-            detection_rule.0.end,
-            detection_rule.1.end,
-            detection_rule.2.end,
-        ) {
-            (None, None, None) => None,
-            (None, Some(x), None) | (Some(x), None, None) | (None, None, Some(x)) => Some(x),
-            (Some(x), Some(y), None) | (Some(x), None, Some(y)) | (None, Some(x), Some(y)) => {
-                Some(max(x, y))
-            }
-            (Some(x), Some(y), Some(z)) => Some(max(max(x, y), z)),
-        };
+        let regions = tree.regions(
+            detection_rule
+                .0
+                .spans
+                .iter()
+                .chain(detection_rule.1.spans.iter())
+                .chain(detection_rule.2.spans.iter()),
+        );
 
         Ok(
             if detection_rule.0.is_matched
                 || detection_rule.1.is_subscript
                 || detection_rule.2.is_function
             {
-                Some((
-                    start.unwrap_or(0) as u64,
-                    String::from(&src[start.unwrap_or(0)..end.unwrap_or(src.len())]),
-                ))
+                detected_regions(
+                    &tree,
+                    src,
+                    &regions,
+                    detection_rule
+                        .0
+                        .matches
+                        .iter()
+                        .chain(detection_rule.1.matches.iter())
+                        .chain(detection_rule.2.matches.iter()),
+                )
             } else {
-                None
+                vec![]
             },
         )
     }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
 }
 
 pub struct IsPythonSubscript {
     is_subscript: bool,
-    start: Option<usize>,
-    end: Option<usize>,
+    matches: Matches,
+    spans: Spans,
     stack: Vec<bool>,
+    tolerance: Tolerance,
+}
+
+impl Default for IsPythonSubscript {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IsPythonSubscript {
     pub fn new() -> Self {
         Self {
             is_subscript: false,
-            start: None,
-            end: None,
+            matches: Matches::default(),
+            spans: Spans::default(),
             stack: vec![true],
+            tolerance: Tolerance::default(),
         }
     }
 
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
     pub fn verify(node: &Node) -> bool {
-        match node.kind() {
-            "subscript" => {
-                if node.child_count() == 4
-                    && node.child(1).unwrap().kind() == "["
-                    && node.child(3).unwrap().kind() == "]"
-                {
-                    if let Some(right) = node.child(2) {
-                        if right.kind() == "slice" {
-                            return true;
-                        }
-                    }
+        if node.kind() == "subscript"
+            && node.child_count() == 4
+            && node.child(1).unwrap().kind() == "["
+            && node.child(3).unwrap().kind() == "]"
+        {
+            if let Some(right) = node.child(2) {
+                if right.kind() == "slice" {
+                    return true;
                 }
             }
-            _ => (),
         }
         false
     }
@@ -131,46 +137,33 @@ impl IsPythonSubscript {
 impl<'a> Rule<'a> for IsPythonSubscript {
     // Match python slice
     // Verify if the parent is subscript
-    // Assert all the children of the parent are valid python nodes
+    // Assert the parsing errors inside the subscript are tolerated
     fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
         if IsPythonSubscript::verify(node) {
             self.stack.push(true);
-
-            self.start = Some(min(
-                self.start.unwrap_or(node.start_abs()),
-                node.start_abs(),
-            ));
-            self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
         }
         Ok(true)
     }
 
     fn leave(&mut self, node: &Node<'a>) -> Result<()> {
-        if node.kind() == "ERROR" || node.text()? == "" {
+        if node.kind() == "ERROR" || node.text()?.is_empty() {
             for c in self.stack.iter_mut() {
                 *c = false;
             }
         }
 
-        if node.child_count() > 1 {
-            if IsPythonSubscript::verify(node) {
-                if self.stack.pop().unwrap_or(false) {
-                    self.start = Some(min(
-                        self.start.unwrap_or(node.start_abs()),
-                        node.start_abs(),
-                    ));
-                    self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
-                    self.is_subscript = true;
-                }
-            }
+        if node.child_count() > 1
+            && IsPythonSubscript::verify(node)
+            && self.stack.pop().is_some()
+            && self.tolerance.accepts_node(node)
+        {
+            self.spans.push(node.start_abs(), node.end_abs());
+            self.is_subscript = true;
+            self.matches.push(node, node.has_error());
         }
 
         if self.is_subscript && self.stack.last() == Some(&true) {
-            self.start = Some(min(
-                self.start.unwrap_or(node.start_abs()),
-                node.start_abs(),
-            ));
-            self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
+            self.spans.push(node.start_abs(), node.end_abs());
         }
 
         Ok(())
@@ -179,47 +172,56 @@ impl<'a> Rule<'a> for IsPythonSubscript {
 
 pub struct IsPythonFunction {
     is_function: bool,
-    start: Option<usize>,
-    end: Option<usize>,
+    matches: Matches,
+    spans: Spans,
+    tolerance: Tolerance,
+}
+
+impl Default for IsPythonFunction {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl IsPythonFunction {
     pub fn new() -> Self {
         Self {
             is_function: false,
-            start: None,
-            end: None,
+            matches: Matches::default(),
+            spans: Spans::default(),
+            tolerance: Tolerance::default(),
         }
     }
+
+    /// Parsing errors accepted inside the matched nodes
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
 }
 
 impl<'a> Rule<'a> for IsPythonFunction {
     // Match python function if in list
     fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
-        match node.kind() {
-            "call" => {
-                if let Some(function) = node.named_child("function") {
-                    if matches!(
-                        function.text().unwrap_or(""),
-                        "requests.get"
-                            | "requests.post"
-                            | "os.system"
-                            | "base64.b64decode"
-                            | "b64decode"
-                            | "subprocess.run"
-                            | "subprocess.Popen"
-                            | "subprocess.call"
-                    ) {
-                        self.is_function = true;
-                        self.start = Some(min(
-                            self.start.unwrap_or(node.start_abs()),
-                            node.start_abs(),
-                        ));
-                        self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
-                    }
+        if node.kind() == "call" {
+            if let Some(function) = node.named_child("function") {
+                if matches!(
+                    function.text().unwrap_or(""),
+                    "requests.get"
+                        | "requests.post"
+                        | "os.system"
+                        | "base64.b64decode"
+                        | "b64decode"
+                        | "subprocess.run"
+                        | "subprocess.Popen"
+                        | "subprocess.call"
+                ) && self.tolerance.accepts_node(node)
+                {
+                    self.is_function = true;
+                    self.matches.push(node, node.has_error());
+                    self.spans.push(node.start_abs(), node.end_abs());
                 }
             }
-            _ => (),
         }
 
         Ok(true)
@@ -0,0 +1,63 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::tree::Tree;
+use crate::visitor::LanguageVisitor;
+use tree_sitter_ruby::LANGUAGE as ruby_language;
+
+fn build_ruby_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&ruby_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+#[derive(Default)]
+pub struct Ruby {
+    tolerance: Tolerance,
+}
+
+impl Parser for Ruby {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_ruby_tree(src)?;
+
+        let mut detection_rule = LanguageVisitor::new(|c| {
+            matches!(
+                c,
+                "method"
+                    | "singleton_method"
+                    | "class"
+                    | "singleton_class"
+                    | "module"
+                    | "if"
+                    | "unless"
+                    | "while"
+                    | "until"
+                    | "for"
+                    | "case"
+                    | "case_match"
+                    | "begin"
+                    | "lambda"
+            )
+        })
+        .with_tolerance(self.tolerance);
+
+        tree.apply(&mut detection_rule)?;
+
+        if detection_rule.is_matched {
+            let regions = tree.regions(detection_rule.spans.iter());
+            Ok(detected_regions(
+                &tree,
+                src,
+                &regions,
+                detection_rule.matches.iter(),
+            ))
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
@@ -1,10 +1,10 @@
 use crate::error::Result;
-use crate::parser::Parser;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
 use crate::tree::Tree;
 use crate::visitor::LanguageVisitor;
 use tree_sitter_sequel::LANGUAGE as sql_language;
 
-fn build_sql_tree(source: &str) -> Result<Tree> {
+fn build_sql_tree(source: &str) -> Result<Tree<'_>> {
     let mut parser = tree_sitter::Parser::new();
     parser.set_language(&sql_language.into())?;
 
@@ -13,26 +13,33 @@ fn build_sql_tree(source: &str) -> Result<Tree> {
 }
 
 #[derive(Default)]
-pub struct Sql;
+pub struct Sql {
+    tolerance: Tolerance,
+}
 
 impl Parser for Sql {
-    fn parse(&mut self, src: &str) -> Result<Option<(u64, String)>> {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
         let tree = build_sql_tree(src)?;
 
-        let mut detection_rule = LanguageVisitor::new(|c| matches!(c, "statement" | "transaction"));
+        let mut detection_rule = LanguageVisitor::new(|c| matches!(c, "statement" | "transaction"))
+            .with_tolerance(self.tolerance);
 
         tree.apply(&mut detection_rule)?;
 
         if detection_rule.is_matched {
-            Ok(Some((
-                detection_rule.start.unwrap_or(0) as u64,
-                String::from(
-                    &src[detection_rule.start.unwrap_or(0)
-                        ..detection_rule.end.unwrap_or(src.len())],
-                ),
-            )))
+            let regions = tree.regions(detection_rule.spans.iter());
+            Ok(detected_regions(
+                &tree,
+                src,
+                &regions,
+                detection_rule.matches.iter(),
+            ))
         } else {
-            Ok(None)
+            Ok(vec![])
         }
     }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
 }
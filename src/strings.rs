@@ -1,169 +1,1355 @@
 use crate::error::Result;
-use std::io::{Read, Seek, SeekFrom};
-use std::marker::PhantomData;
-use std::result::Result as StdResult;
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Error as IoError, Read, Seek};
 
 pub type Utf16le = u16;
 
+#[derive(Debug, Clone, Copy)]
+pub struct Utf16be(pub u16);
+
+pub type Utf32le = u32;
+
+/// Max number of bytes used by a char, whatever the encoding
+const MAX_CHAR_LEN: usize = 4;
+
+/// Encoding of the strings in the input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Encoding {
+    Utf8,
+    Utf16le,
+    Utf16be,
+    Utf32le,
+}
+
+/// Run an expression with `$U` set to the decoder of the encoding
+macro_rules! with_decoder {
+    ($encoding:expr, $U:ident => $body:expr) => {
+        match $encoding {
+            Encoding::Utf8 => {
+                type $U = u8;
+                $body
+            }
+            Encoding::Utf16le => {
+                type $U = Utf16le;
+                $body
+            }
+            Encoding::Utf16be => {
+                type $U = Utf16be;
+                $body
+            }
+            Encoding::Utf32le => {
+                type $U = Utf32le;
+                $body
+            }
+        }
+    };
+}
+
+impl Encoding {
+    /// Number of bytes used by a decoded string in the input
+    pub fn encoded_len(self, s: &str) -> u64 {
+        with_decoder!(self, U => encoded_len::<U>(s))
+    }
+
+    /// Size of a code unit
+    pub fn unit_len(self) -> usize {
+        with_decoder!(self, U => size_of::<U>())
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encoding::Utf8 => "utf8",
+            Encoding::Utf16le => "utf16le",
+            Encoding::Utf16be => "utf16be",
+            Encoding::Utf32le => "utf32le",
+        })
+    }
+}
+
 pub trait Decode
 where
     Self: Sized,
 {
-    fn from_bytes(value: &[u8]) -> Result<Self>;
-    fn from_vec(buf: Vec<Self>) -> Result<String>;
+    const ENCODING: Encoding;
+
+    /// Decode the char at the start of `value`
+    /// Return the char and the number of bytes used,
+    /// None if `value` doesn't start with a valid char
+    fn decode(value: &[u8]) -> Option<(char, usize)>;
+    /// True if `value` starts in the middle of a char
+    fn is_continuation(value: &[u8]) -> bool;
+    /// Number of bytes used by the char in the input
+    fn encoded_len(c: char) -> usize;
+}
+
+/// Number of bytes used by a decoded string in the input
+pub fn encoded_len<U: Decode>(s: &str) -> u64 {
+    s.chars().map(|c| U::encoded_len(c) as u64).sum()
+}
+
+fn utf16_decode(unit: impl Fn(usize) -> Option<u16>) -> Option<(char, usize)> {
+    match std::char::decode_utf16([unit(0)?]).next()? {
+        Ok(c) => Some((c, 2)),
+        // surrogate pair
+        Err(_) => Some((
+            std::char::decode_utf16([unit(0)?, unit(1)?]).next()?.ok()?,
+            4,
+        )),
+    }
 }
 
 impl Decode for Utf16le {
-    fn from_bytes(value: &[u8]) -> Result<u16> {
-        Ok(u16::from_le_bytes(value.try_into()?))
+    const ENCODING: Encoding = Encoding::Utf16le;
+
+    fn decode(value: &[u8]) -> Option<(char, usize)> {
+        utf16_decode(|i| {
+            Some(u16::from_le_bytes(
+                value.get(i * 2..i * 2 + 2)?.try_into().ok()?,
+            ))
+        })
+    }
+
+    fn is_continuation(value: &[u8]) -> bool {
+        matches!(value, [_, 0xdc..=0xdf, ..])
     }
 
-    fn from_vec(buf: Vec<Self>) -> Result<String> {
-        Ok(std::char::decode_utf16(buf.into_iter()).collect::<StdResult<String, _>>()?)
+    fn encoded_len(c: char) -> usize {
+        c.len_utf16() * size_of::<u16>()
     }
 }
 
-impl Decode for u8 {
-    fn from_bytes(value: &[u8]) -> Result<u8> {
-        Ok(u8::from_le_bytes(value.try_into()?))
+impl Decode for Utf16be {
+    const ENCODING: Encoding = Encoding::Utf16be;
+
+    fn decode(value: &[u8]) -> Option<(char, usize)> {
+        utf16_decode(|i| {
+            Some(u16::from_be_bytes(
+                value.get(i * 2..i * 2 + 2)?.try_into().ok()?,
+            ))
+        })
     }
 
-    fn from_vec(buf: Vec<Self>) -> Result<String> {
-        Ok(String::from_utf8(buf)?)
+    fn is_continuation(value: &[u8]) -> bool {
+        matches!(value, [0xdc..=0xdf, _, ..])
+    }
+
+    fn encoded_len(c: char) -> usize {
+        c.len_utf16() * size_of::<u16>()
     }
 }
 
-fn is_printable(c: u64) -> bool {
-    c == 0xd || c == 0xa || (c >= 0x20 && c <= 0x7e)
+impl Decode for Utf32le {
+    const ENCODING: Encoding = Encoding::Utf32le;
+
+    fn decode(value: &[u8]) -> Option<(char, usize)> {
+        let c = char::from_u32(u32::from_le_bytes(value.get(..4)?.try_into().ok()?))?;
+        Some((c, 4))
+    }
+
+    fn is_continuation(_value: &[u8]) -> bool {
+        false
+    }
+
+    fn encoded_len(_c: char) -> usize {
+        size_of::<u32>()
+    }
 }
 
-fn decode_until_null_byte<T: Read + Seek, U: Decode + Into<u64> + Copy>(
-    stream: &mut T,
-) -> Result<String> {
-    let position = stream.seek(SeekFrom::Current(0))?;
-    let mut find_null_term = false;
-    let mut result = vec![];
-    while !find_null_term {
-        let mut buffer = [0; 1024];
-        stream.read(&mut buffer)?;
+impl Decode for u8 {
+    const ENCODING: Encoding = Encoding::Utf8;
 
-        for i in (0..1024).step_by(size_of::<U>()) {
-            let tmp_char = U::from_bytes(&buffer[i..(i + size_of::<U>())])?;
-            if !is_printable(tmp_char.into()) {
-                find_null_term = true;
-                break;
-            }
+    fn decode(value: &[u8]) -> Option<(char, usize)> {
+        let len = match value.first()? {
+            0x00..=0x7f => 1,
+            0xc2..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf4 => 4,
+            _ => return None,
+        };
+        let c = std::str::from_utf8(value.get(..len)?)
+            .ok()?
+            .chars()
+            .next()?;
+        Some((c, len))
+    }
+
+    fn is_continuation(value: &[u8]) -> bool {
+        matches!(value, [0x80..=0xbf, ..])
+    }
+
+    fn encoded_len(c: char) -> usize {
+        c.len_utf8()
+    }
+}
 
-            result.push(tmp_char);
+/// Class of chars accepted in a string
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Charset {
+    /// Printable ASCII chars, CR and LF
+    #[default]
+    Ascii,
+    /// Printable ASCII chars, CR, LF and TAB
+    AsciiTab,
+    /// Printable ASCII chars, CR, LF, TAB
+    /// and any unicode letter, number, punctuation or symbol
+    Unicode,
+}
+
+impl Charset {
+    pub fn is_printable(self, c: char) -> bool {
+        match c {
+            '\r' | '\n' | ' '..='~' => true,
+            '\t' => self != Charset::Ascii,
+            _ => self == Charset::Unicode && is_unicode_printable(c),
         }
     }
+}
 
-    stream.seek(SeekFrom::Start(
-        position + (size_of::<U>() as u64) * (result.len() as u64),
-    ))?;
-    U::from_vec(result)
+fn is_unicode_printable(c: char) -> bool {
+    c.is_alphanumeric()
+        || matches!(
+            c as u32,
+            // latin-1 punctuation and symbols
+            0xa0..=0xbf | 0xd7 | 0xf7 |
+            // general punctuation
+            0x2010..=0x205e |
+            // currency, letterlike, arrows, mathematical and technical symbols
+            0x20a0..=0x20cf | 0x2100..=0x23ff |
+            // box drawing, shapes and dingbats
+            0x2500..=0x27bf |
+            // CJK punctuation
+            0x3000..=0x303f |
+            // fullwidth punctuation
+            0xff01..=0xff65
+        )
 }
 
-pub struct StringsIterator<T: Read + Seek, U> {
-    buffer: T,
-    step: usize,
-    encoding: PhantomData<U>,
+/// Strings extraction parameters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// Number of chars skipped while looking for a printable char,
+    /// strings shorter than `step + 1` chars may be missed
+    pub step: usize,
+    /// Min number of chars of a string
+    pub min_length: usize,
+    /// Max number of chars of a string, longer strings are split in windows,
+    /// raised to `min_length` when it's lower
+    pub max_length: usize,
+    /// Number of chars shared by consecutive windows,
+    /// at most half of `max_length`
+    pub overlap: usize,
+    /// Max number of bytes between two runs joined in a single string,
+    /// runs shorter than `step + 1` chars may be missed at the start of a string
+    pub gap: usize,
+    /// Chars accepted in a string
+    pub charset: Charset,
+    /// Encodings extracted in the same pass
+    pub encodings: Vec<Encoding>,
 }
 
-impl<T: Read + Seek, U> StringsIterator<T, U> {
-    pub fn new(buffer: T, step: usize) -> Self {
+impl Options {
+    /// Only strings longer than `step` are reported
+    pub fn new(step: usize) -> Self {
         Self {
-            buffer,
             step,
-            encoding: PhantomData,
+            min_length: step + 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_min_length(self, min_length: usize) -> Self {
+        Self { min_length, ..self }
+    }
+
+    /// Max number of chars of a window,
+    /// a truncated string is always long enough to be reported
+    fn window_length(&self) -> usize {
+        self.max_length.max(self.min_length)
+    }
+
+    pub fn with_gap(self, gap: usize) -> Self {
+        Self { gap, ..self }
+    }
+
+    pub fn with_max_length(self, max_length: usize, overlap: usize) -> Self {
+        Self {
+            max_length,
+            overlap,
+            ..self
         }
     }
+
+    pub fn with_charset(self, charset: Charset) -> Self {
+        Self { charset, ..self }
+    }
+
+    pub fn with_encodings(self, encodings: Vec<Encoding>) -> Self {
+        Self { encodings, ..self }
+    }
 }
 
-impl<'a, T: Read + Seek, U: Decode + Into<u64> + Copy> Iterator for StringsIterator<T, U> {
-    type Item = (u64, String);
+impl From<usize> for Options {
+    fn from(step: usize) -> Self {
+        Self::new(step)
+    }
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        next_strings::<T, U>(&mut self.buffer, self.step).ok()
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            step: 20,
+            min_length: 21,
+            max_length: 1024 * 1024,
+            overlap: 4096,
+            gap: 0,
+            charset: Charset::default(),
+            encodings: vec![Encoding::Utf8],
+        }
+    }
+}
+
+/// Random access to the bytes of the input
+pub trait Source {
+    /// Copy the bytes at `index` into `buf`
+    /// Return the number of bytes copied, less than `buf.len()` at the end of the input
+    fn read_at(&mut self, index: u64, buf: &mut [u8]) -> Result<usize>;
+}
+
+impl<T: Read + Seek> Source for T {
+    fn read_at(&mut self, index: u64, buf: &mut [u8]) -> Result<usize> {
+        // relative seek keeps the content of buffered readers
+        let current = self.stream_position()?;
+        self.seek_relative(index as i64 - current as i64)?;
+
+        let mut read = 0;
+        while read < buf.len() {
+            match self.read(&mut buf[read..])? {
+                0 => break,
+                n => read += n,
+            }
+        }
+        Ok(read)
+    }
+}
+
+/// Source over an in memory buffer
+pub struct SliceSource<'a>(pub &'a [u8]);
+
+impl Source for SliceSource<'_> {
+    fn read_at(&mut self, index: u64, buf: &mut [u8]) -> Result<usize> {
+        let data = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.0.get(index..))
+            .unwrap_or_default();
+        let read = data.len().min(buf.len());
+        buf[..read].copy_from_slice(&data[..read]);
+        Ok(read)
+    }
+}
+
+/// Source over a stream that can't seek (stdin, pipes, decompressors...)
+///
+/// The last `lookback` bytes read are kept to be read again
+pub struct StreamSource<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Offset of the first byte of the buffer in the stream
+    base: u64,
+    lookback: usize,
+    eof: bool,
+}
+
+impl<R: Read> StreamSource<R> {
+    pub fn new(reader: R, lookback: usize) -> Self {
+        Self {
+            reader,
+            buffer: Vec::new(),
+            base: 0,
+            lookback,
+            eof: false,
+        }
+    }
+}
+
+impl<R: Read> Source for StreamSource<R> {
+    fn read_at(&mut self, index: u64, buf: &mut [u8]) -> Result<usize> {
+        if index < self.base {
+            return Err(IoError::other("offset out of the lookback buffer").into());
+        }
+
+        let end = index + buf.len() as u64;
+        while self.base + (self.buffer.len() as u64) < end && !self.eof {
+            // forget what is out of the lookback
+            if self.buffer.len() >= 2 * self.lookback {
+                let forget = (self.buffer.len() - self.lookback).min((index - self.base) as usize);
+                self.buffer.drain(..forget);
+                self.base += forget as u64;
+            }
+
+            let filled = self.buffer.len();
+            self.buffer.resize(filled + BLOCK_SIZE as usize, 0);
+            let read = self.reader.read(&mut self.buffer[filled..])?;
+            self.buffer.truncate(filled + read);
+            self.eof = read == 0;
+        }
+
+        let data = self
+            .buffer
+            .get((index - self.base) as usize..)
+            .unwrap_or_default();
+        let read = data.len().min(buf.len());
+        buf[..read].copy_from_slice(&data[..read]);
+        Ok(read)
+    }
+}
+
+/// Decode the char starting at `index`
+fn char_at<S: Source, U: Decode>(source: &mut S, index: u64) -> Result<Option<(char, u64)>> {
+    let mut bytes = [0; MAX_CHAR_LEN];
+    let read = source.read_at(index, &mut bytes)?;
+    Ok(U::decode(&bytes[..read]).map(|(c, len)| (c, len as u64)))
+}
+
+/// Find the start of the char containing `index`
+fn char_start<S: Source, U: Decode>(source: &mut S, index: u64) -> Result<u64> {
+    let size = size_of::<U>() as u64;
+    let mut bytes = [0; MAX_CHAR_LEN];
+    let mut start = index;
+    while start >= size && index - start + size < MAX_CHAR_LEN as u64 {
+        let read = source.read_at(start, &mut bytes[..size as usize])?;
+        if !U::is_continuation(&bytes[..read]) {
+            break;
+        }
+        start -= size;
+    }
+    Ok(start)
+}
+
+/// Find the start of the printable char that ends at `index`, not before `bound`
+fn printable_char_before<S: Source, U: Decode>(
+    source: &mut S,
+    index: u64,
+    bound: u64,
+    charset: Charset,
+) -> Result<Option<u64>> {
+    let size = size_of::<U>() as u64;
+    if index < bound + size {
+        return Ok(None);
+    }
+    let start = char_start::<S, U>(source, index - size)?;
+    Ok(match char_at::<S, U>(source, start)? {
+        Some((c, len)) if start >= bound && start + len == index && charset.is_printable(c) => {
+            Some(start)
+        }
+        _ => None,
+    })
+}
+
+/// Read the printable chars from `position`, at most `options.max_length` of them
+///
+/// Runs separated by at most `options.gap` bytes are joined
+///
+/// Returns the string and the offset following it
+fn read_string<S: Source, U: Decode>(
+    source: &mut S,
+    position: u64,
+    options: &Options,
+) -> Result<(Candidate, u64)> {
+    let mut text = String::new();
+    let mut gaps = Vec::new();
+    let mut length = 0;
+    let mut truncated = false;
+    let mut end = position;
+    while let Some((c, len, skipped)) = joined_char_at::<S, U>(source, end, options)? {
+        if length == options.window_length() {
+            truncated = true;
+            break;
+        }
+        if skipped > 0 {
+            gaps.push((text.len(), skipped));
+        }
+        text.push(c);
+        length += 1;
+        end += skipped + len;
+    }
+
+    Ok((
+        Candidate {
+            offset: position,
+            text,
+            gaps,
+            encoding: U::ENCODING,
+            continued: false,
+            truncated,
+        },
+        end,
+    ))
+}
+
+/// Find the start of the printable char that ends at `index`,
+/// or at most `options.gap` bytes before it, not before `bound`
+fn joined_char_before<S: Source, U: Decode>(
+    source: &mut S,
+    index: u64,
+    bound: u64,
+    options: &Options,
+) -> Result<Option<u64>> {
+    let size = size_of::<U>();
+    for skipped in (0..=options.gap as u64).step_by(size) {
+        if index < bound + skipped {
+            break;
+        }
+        if let Some(start) =
+            printable_char_before::<S, U>(source, index - skipped, bound, options.charset)?
+        {
+            return Ok(Some(start));
+        }
     }
+    Ok(None)
 }
 
-pub fn next_strings<T: Read + Seek, U: Decode + Into<u64> + Copy>(
-    buf: &mut T,
-    step: usize,
-) -> Result<(u64, String)> {
-    let increment = step * size_of::<U>();
-    let mut cursor;
+/// Decode the printable char at `index`, or at most `options.gap` bytes after it
+///
+/// Returns the char, its length and the number of bytes skipped
+fn joined_char_at<S: Source, U: Decode>(
+    source: &mut S,
+    index: u64,
+    options: &Options,
+) -> Result<Option<(char, u64, u64)>> {
+    let size = size_of::<U>();
+    for skipped in (0..=options.gap as u64).step_by(size) {
+        match char_at::<S, U>(source, index + skipped)? {
+            Some((c, len)) if options.charset.is_printable(c) => {
+                return Ok(Some((c, len, skipped)))
+            }
+            _ => (),
+        }
+    }
+    Ok(None)
+}
+
+/// Find the next string of the source aligned on the cursor
+///
+/// The cursor is the position of the end of the last string found,
+/// no string is looked for once the cursor reaches `limit`
+pub fn next_strings<S: Source, U: Decode>(
+    source: &mut S,
+    cursor: &mut u64,
+    limit: u64,
+    options: &Options,
+) -> Result<Option<Candidate>> {
+    let size = size_of::<U>() as u64;
+    let increment = options.step as u64 * size;
     loop {
-        cursor = buf.seek(SeekFrom::Current(increment as i64))?;
+        if *cursor >= limit {
+            return Ok(None);
+        }
 
-        let mut find_printable = false;
-        for i in 0..(size_of::<U>() as u64) {
-            let mut char_buffer = vec![0; size_of::<U>()];
-            buf.read_exact(char_buffer.as_mut_slice())?;
+        let probe = *cursor + increment;
 
-            let char = U::from_bytes(char_buffer.as_slice())?;
+        let mut bytes = [0; MAX_CHAR_LEN];
+        let read = source.read_at(probe, &mut bytes)?;
+        if read < size as usize {
+            return Ok(None);
+        }
 
-            if is_printable(char.into()) {
-                find_printable = true;
-                break;
+        // find a printable char, the probe may be in the middle of a char
+        let (start, decoded) = if U::is_continuation(&bytes[..read]) {
+            let start = char_start::<S, U>(source, probe)?;
+            (start, char_at::<S, U>(source, start)?)
+        } else {
+            let decoded = U::decode(&bytes[..read]).map(|(c, len)| (c, len as u64));
+            (probe, decoded)
+        };
+        let found = match decoded {
+            Some((c, len)) if start + len > probe && options.charset.is_printable(c) => start,
+            _ => {
+                *cursor = probe + size;
+                continue;
             }
+        };
+
+        // try to find what I missed
+        let mut position = found;
+        while let Some(previous) = joined_char_before::<S, U>(source, position, *cursor, options)? {
+            position = previous;
+        }
+
+        // read until the first non printable char
+        let (candidate, end) = read_string::<S, U>(source, position, options)?;
 
-            buf.seek(SeekFrom::Start(cursor + i + 1))?;
+        *cursor = end;
+        if candidate.truncated || candidate.text.chars().count() >= options.min_length {
+            return Ok(Some(candidate));
         }
+    }
+}
 
-        if find_printable {
-            // my cursor is the current minus the one matched
-            cursor = buf.seek(SeekFrom::Current(0))? - size_of::<U>() as u64;
+/// A printable string found in the input
+///
+/// Strings longer than `Options::max_length` are split in overlapping windows,
+/// yielded one after the other
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    /// Offset of the string in the input
+    pub offset: u64,
+    /// Decoded string
+    pub text: String,
+    /// Byte index in `text` and number of bytes skipped in the input before it,
+    /// for each gap between joined runs
+    pub gaps: Vec<(usize, u64)>,
+    /// Encoding of the string in the input
+    pub encoding: Encoding,
+    /// Window continuing the previous candidate
+    pub continued: bool,
+    /// The string goes on in the next candidate
+    pub truncated: bool,
+}
+
+impl Candidate {
+    /// Offset in the input of the char at a byte index of the decoded string
+    pub fn source_offset(&self, index: usize) -> u64 {
+        self.source_end(index)
+            + self
+                .gaps
+                .iter()
+                .filter(|(gap, _)| *gap == index)
+                .map(|(_, skipped)| skipped)
+                .sum::<u64>()
+    }
 
-            // try to find what I missed
-            buf.seek(SeekFrom::Start(cursor - increment as u64))?;
+    /// Offset in the input following the chars before a byte index of the decoded string
+    pub fn source_end(&self, index: usize) -> u64 {
+        self.offset
+            + self.encoding.encoded_len(&self.text[..index])
+            + self
+                .gaps
+                .iter()
+                .take_while(|(gap, _)| *gap < index)
+                .map(|(_, skipped)| skipped)
+                .sum::<u64>()
+    }
 
-            // read back to my increment and find first non printable char
-            let mut buffer = vec![0; increment as usize];
-            buf.read_exact(&mut buffer)?;
+    /// `source_offset` and `source_end` of sorted and disjoint ranges of the decoded string,
+    /// in a single pass over the string
+    pub fn source_ranges(&self, ranges: &[(usize, usize)]) -> Vec<(u64, u64)> {
+        let mut index = 0;
+        // offset in the input of the chars before index, with the gaps before index
+        let mut position = self.offset;
+        let mut gaps = self.gaps.iter().peekable();
+        let mut source_ranges = Vec::with_capacity(ranges.len());
 
-            let mut position = cursor;
-            for i in (0..increment).step_by(size_of::<U>()).rev() {
-                // find non printable !
-                if is_printable(U::from_bytes(&buffer[i..(i + size_of::<U>())])?.into()) {
-                    position -= size_of::<U>() as u64;
-                } else {
-                    break;
-                }
+        for (start, end) in ranges {
+            position += self.encoding.encoded_len(&self.text[index..*start]);
+            while let Some((_, skipped)) = gaps.next_if(|(gap, _)| gap < start) {
+                position += skipped;
             }
+            // a gap right before the range is not part of it
+            let source_start = position
+                + gaps
+                    .clone()
+                    .take_while(|(gap, _)| gap == start)
+                    .map(|(_, skipped)| skipped)
+                    .sum::<u64>();
 
-            buf.seek(SeekFrom::Start(position))?;
+            position += self.encoding.encoded_len(&self.text[*start..*end]);
+            while let Some((_, skipped)) = gaps.next_if(|(gap, _)| gap < end) {
+                position += skipped;
+            }
+            index = *end;
+            source_ranges.push((source_start, position));
+        }
 
-            if let Ok(str) = decode_until_null_byte::<T, U>(buf) {
-                if str.len() > step {
-                    return Ok((position, str));
+        source_ranges
+    }
+
+    /// Byte index in the decoded string of the first char not before an offset in the input
+    pub fn text_index(&self, offset: u64) -> usize {
+        let mut position = self.offset;
+        let mut gaps = self.gaps.iter().peekable();
+        self.text
+            .char_indices()
+            .find(|(index, c)| {
+                if let Some((_, skipped)) = gaps.next_if(|(gap, _)| gap == index) {
+                    position += skipped;
                 }
+                let found = position >= offset;
+                position += self.encoding.encoded_len(c.encode_utf8(&mut [0; 4]));
+                found
+            })
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    /// Offset of the window following a truncated candidate,
+    /// it starts `overlap` chars before the end of this one
+    fn next_window(&self, options: &Options) -> u64 {
+        let overlap = options.overlap.min(options.window_length() / 2);
+        let index = match overlap {
+            0 => self.text.len(),
+            _ => self
+                .text
+                .char_indices()
+                .rev()
+                .nth(overlap - 1)
+                .map_or(0, |(index, _)| index),
+        };
+        self.source_offset(index)
+    }
+}
+
+/// `next_strings` that only yields strings starting in `start..end`
+///
+/// Strings are read past `end` until their end, and a string that
+/// started before `start` is left to the range that contains its start.
+/// The search stops at `limit`, and may be resumed later
+fn next_range_strings<S: Source, U: Decode>(
+    source: &mut S,
+    lane: &mut Lane,
+    start: u64,
+    end: u64,
+    limit: u64,
+    options: &Options,
+) -> Result<Option<Candidate>> {
+    // the windows of a string belong to the range of its first window
+    if let Some(position) = lane.window.take() {
+        let (mut candidate, string_end) = read_string::<S, U>(source, position, options)?;
+        candidate.continued = true;
+        if candidate.truncated {
+            lane.window = Some(candidate.next_window(options));
+        }
+        lane.cursor = string_end;
+        return Ok(Some(candidate));
+    }
+
+    while let Some(candidate) =
+        next_strings::<S, U>(source, &mut lane.cursor, limit.min(end), options)?
+    {
+        // the string belongs to the next range
+        if candidate.offset >= end {
+            lane.cursor = end;
+            return Ok(None);
+        }
+
+        // the string was cut by the start of the range,
+        // only the first one found from there may continue a char before it
+        let first = std::mem::replace(&mut lane.first, false);
+        if start > 0
+            && (candidate.offset < start
+                || first
+                    && joined_char_before::<S, U>(source, candidate.offset, 0, options)?
+                        .is_some_and(|previous| previous < start))
+        {
+            // the windows of the string are left to the previous range too
+            let mut window = candidate;
+            while window.truncated {
+                let (next, string_end) =
+                    read_string::<S, U>(source, window.next_window(options), options)?;
+                lane.cursor = string_end;
+                window = next;
             }
+            continue;
+        }
+
+        if candidate.truncated {
+            lane.window = Some(candidate.next_window(options));
+        }
+        return Ok(Some(candidate));
+    }
+    Ok(None)
+}
+
+/// Max number of bytes a lane is scanned ahead of the others,
+/// it bounds the lookback needed to scan a stream
+const BLOCK_SIZE: u64 = 64 * 1024;
+
+/// Scanning state of an encoding on an alignment of its code units
+struct Lane {
+    encoding: Encoding,
+    cursor: u64,
+    /// No more strings
+    done: bool,
+    /// Offset of the next window of a truncated string
+    window: Option<u64>,
+    pending: Option<Candidate>,
+    /// No string was found since the start of the range
+    first: bool,
+}
+
+/// Scan every encoding, and every alignment of its code units, independently
+/// and merge the strings found by offset
+///
+/// The windows of a truncated string are yielded together,
+/// as if it were a single string
+struct Scanner {
+    lanes: Vec<Lane>,
+    start: u64,
+    end: u64,
+    options: Options,
+}
+
+impl Scanner {
+    /// Scan from `position` until the end of the source
+    fn new(options: Options, position: u64) -> Self {
+        Self {
+            start: 0,
+            ..Self::with_range(options, position, u64::MAX)
+        }
+    }
+
+    fn with_range(options: Options, start: u64, end: u64) -> Self {
+        Self {
+            lanes: options
+                .encodings
+                .iter()
+                .flat_map(|&encoding| {
+                    (0..encoding.unit_len() as u64).map(move |alignment| Lane {
+                        encoding,
+                        cursor: start + alignment,
+                        done: false,
+                        window: None,
+                        pending: None,
+                        first: true,
+                    })
+                })
+                .collect(),
+            start,
+            end,
+            options,
+        }
+    }
+
+    fn next<S: Source>(&mut self, source: &mut S) -> Result<Option<Candidate>> {
+        // the windows of a truncated string come first
+        if let Some(lane) = self
+            .lanes
+            .iter_mut()
+            .find(|lane| lane.window.is_some() && lane.pending.is_none())
+        {
+            return with_decoder!(lane.encoding, U => next_range_strings::<S, U>(
+                source,
+                lane,
+                self.start,
+                self.end,
+                u64::MAX,
+                &self.options,
+            ));
+        }
+
+        loop {
+            let best = self
+                .lanes
+                .iter()
+                .filter_map(|lane| lane.pending.as_ref().map(|pending| pending.offset))
+                .min();
+
+            // scan the lanes in lockstep, until none of them may find a string before the best one
+            let Some(lane) = self
+                .lanes
+                .iter_mut()
+                .filter(|lane| {
+                    !lane.done
+                        && lane.pending.is_none()
+                        && best.is_none_or(|best| lane.cursor <= best)
+                })
+                .min_by_key(|lane| lane.cursor)
+            else {
+                break;
+            };
+
+            let limit = best
+                .map_or(u64::MAX, |best| best + 1)
+                .min(lane.cursor.saturating_add(BLOCK_SIZE));
+            lane.pending = with_decoder!(lane.encoding, U => next_range_strings::<S, U>(
+                source,
+                lane,
+                self.start,
+                self.end,
+                limit,
+                &self.options,
+            ))?;
+            lane.done = lane.pending.is_none() && (lane.cursor < limit || lane.cursor >= self.end);
+        }
+
+        Ok(self
+            .lanes
+            .iter_mut()
+            .filter(|lane| lane.pending.is_some())
+            .min_by_key(|lane| lane.pending.as_ref().map(|pending| pending.offset))
+            .and_then(|lane| lane.pending.take()))
+    }
+}
+
+pub struct StringsIterator<T: Read + Seek> {
+    buffer: T,
+    scanner: Option<Scanner>,
+    options: Options,
+}
+
+impl<T: Read + Seek> StringsIterator<T> {
+    pub fn new(buffer: T, step: usize) -> Self {
+        Self::with_options(buffer, Options::new(step))
+    }
+
+    pub fn with_options(buffer: T, options: Options) -> Self {
+        Self {
+            buffer,
+            scanner: None,
+            options,
+        }
+    }
+}
+
+impl<T: Read + Seek> Iterator for StringsIterator<T> {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // start from the current position of the stream
+        if self.scanner.is_none() {
+            let start = self.buffer.stream_position().ok()?;
+            self.scanner = Some(Scanner::new(self.options.clone(), start));
+        }
+        self.scanner.as_mut()?.next(&mut self.buffer).ok().flatten()
+    }
+}
+
+/// Strings iterator working directly on an in memory buffer
+/// (a slice, a vector or a memory mapped file)
+///
+/// It yields the same items than `StringsIterator`
+/// but walks the buffer linearly without any seek
+pub struct SliceStringsIterator<B> {
+    buffer: B,
+    scanner: Scanner,
+}
+
+impl<B: AsRef<[u8]>> SliceStringsIterator<B> {
+    pub fn new(buffer: B, step: usize) -> Self {
+        Self::with_options(buffer, Options::new(step))
+    }
+
+    pub fn with_options(buffer: B, options: Options) -> Self {
+        Self::with_range(buffer, options, 0, u64::MAX)
+    }
+
+    /// Only yield strings starting in `start..end`
+    ///
+    /// Strings are read past `end` until their end, and a string that
+    /// started before `start` is left to the range that contains its start,
+    /// so consecutive ranges yield every string exactly once
+    pub fn with_range(buffer: B, options: Options, start: u64, end: u64) -> Self {
+        Self {
+            buffer,
+            scanner: Scanner::with_range(options, start, end),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> Iterator for SliceStringsIterator<B> {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner
+            .next(&mut SliceSource(self.buffer.as_ref()))
+            .ok()
+            .flatten()
+    }
+}
+
+/// Strings iterator over a stream that can't seek
+///
+/// It yields the same items than `StringsIterator`,
+/// reading the stream once with a bounded lookback
+pub struct StreamStringsIterator<R> {
+    source: StreamSource<R>,
+    scanner: Scanner,
+}
+
+impl<R: Read> StreamStringsIterator<R> {
+    pub fn new(reader: R, step: usize) -> Self {
+        Self::with_options(reader, Options::new(step))
+    }
+
+    pub fn with_options(reader: R, options: Options) -> Self {
+        // lanes stay within a block and a window of each other
+        let lookback =
+            2 * (BLOCK_SIZE as usize + MAX_CHAR_LEN * (options.window_length() + options.step + 1));
+        Self {
+            source: StreamSource::new(reader, lookback),
+            scanner: Scanner::new(options, 0),
+        }
+    }
+}
+
+impl<R: Read> Iterator for StreamStringsIterator<R> {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.scanner.next(&mut self.source).ok().flatten()
+    }
+}
+
+/// Strings iterator over a file
+///
+/// Regular files are memory mapped and scanned with `SliceStringsIterator`,
+/// other seekable files (devices...) fall back on a buffered `StringsIterator`,
+/// and anything else (pipes, sockets...) is streamed with `StreamStringsIterator`
+pub enum FileStringsIterator {
+    Mapped(SliceStringsIterator<Mmap>),
+    Buffered(StringsIterator<BufReader<File>>),
+    Streamed(StreamStringsIterator<File>),
+}
+
+impl FileStringsIterator {
+    pub fn new(mut file: File, options: Options) -> Result<Self> {
+        if let Some(mmap) = map_file(&file)? {
+            return Ok(Self::Mapped(SliceStringsIterator::with_options(
+                mmap, options,
+            )));
+        }
+        if file.stream_position().is_err() {
+            return Ok(Self::Streamed(StreamStringsIterator::with_options(
+                file, options,
+            )));
+        }
+        Ok(Self::Buffered(StringsIterator::with_options(
+            BufReader::new(file),
+            options,
+        )))
+    }
+}
+
+/// Memory map a regular file
+///
+/// Return None if the file can't be mapped
+pub fn map_file(file: &File) -> Result<Option<Mmap>> {
+    if !file.metadata()?.is_file() {
+        return Ok(None);
+    }
+    // Safety: the mapping is read only, the file must not be truncated while scanning
+    Ok(unsafe { Mmap::map(file) }.ok())
+}
+
+impl Iterator for FileStringsIterator {
+    type Item = Candidate;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Mapped(iter) => iter.next(),
+            Self::Buffered(iter) => iter.next(),
+            Self::Streamed(iter) => iter.next(),
         }
     }
 }
 
 pub trait IterUtf16leStrings {
-    fn iter_utf16le_strings(self, step: usize) -> StringsIterator<Self, Utf16le>
+    fn iter_utf16le_strings(self, step: usize) -> StringsIterator<Self>
     where
         Self: Read + Seek + Sized;
 }
 
 impl<T: Read + Seek> IterUtf16leStrings for T {
-    fn iter_utf16le_strings(self, step: usize) -> StringsIterator<Self, Utf16le> {
-        StringsIterator::new(self, step)
+    fn iter_utf16le_strings(self, step: usize) -> StringsIterator<Self> {
+        StringsIterator::with_options(
+            self,
+            Options::new(step).with_encodings(vec![Utf16le::ENCODING]),
+        )
+    }
+}
+
+pub trait IterUtf16beStrings {
+    fn iter_utf16be_strings(self, step: usize) -> StringsIterator<Self>
+    where
+        Self: Read + Seek + Sized;
+}
+
+impl<T: Read + Seek> IterUtf16beStrings for T {
+    fn iter_utf16be_strings(self, step: usize) -> StringsIterator<Self> {
+        StringsIterator::with_options(
+            self,
+            Options::new(step).with_encodings(vec![Utf16be::ENCODING]),
+        )
+    }
+}
+
+pub trait IterUtf32leStrings {
+    fn iter_utf32le_strings(self, step: usize) -> StringsIterator<Self>
+    where
+        Self: Read + Seek + Sized;
+}
+
+impl<T: Read + Seek> IterUtf32leStrings for T {
+    fn iter_utf32le_strings(self, step: usize) -> StringsIterator<Self> {
+        StringsIterator::with_options(
+            self,
+            Options::new(step).with_encodings(vec![Utf32le::ENCODING]),
+        )
     }
 }
 
 pub trait IterStrings {
-    fn iter_strings(self, step: usize) -> StringsIterator<Self, u8>
+    fn iter_strings(self, step: usize) -> StringsIterator<Self>
     where
         Self: Read + Seek + Sized;
 }
 
 impl<T: Read + Seek> IterStrings for T {
-    fn iter_strings(self, step: usize) -> StringsIterator<Self, u8> {
-        StringsIterator::new(self, step)
+    fn iter_strings(self, step: usize) -> StringsIterator<Self> {
+        StringsIterator::with_options(self, Options::new(step).with_encodings(vec![u8::ENCODING]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(encoding: Encoding, s: &str) -> Vec<u8> {
+        match encoding {
+            Encoding::Utf8 => s.as_bytes().to_vec(),
+            Encoding::Utf16le => s.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Encoding::Utf16be => s.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Encoding::Utf32le => s.chars().flat_map(|c| (c as u32).to_le_bytes()).collect(),
+        }
+    }
+
+    fn strings(buffer: &[u8], options: Options) -> Vec<Candidate> {
+        SliceStringsIterator::with_options(buffer, options).collect()
+    }
+
+    #[test]
+    fn source_offsets_in_every_encoding() {
+        let text = "echo 'café crème' > menu.txt";
+        for encoding in [
+            Encoding::Utf8,
+            Encoding::Utf16le,
+            Encoding::Utf16be,
+            Encoding::Utf32le,
+        ] {
+            let mut buffer = vec![0; 5];
+            buffer.extend(encode(encoding, text));
+            buffer.extend([0; 4]);
+
+            let candidates = strings(
+                &buffer,
+                Options::new(4)
+                    .with_charset(Charset::Unicode)
+                    .with_encodings(vec![encoding]),
+            );
+            assert_eq!(candidates.len(), 1, "{encoding}");
+            let candidate = &candidates[0];
+            assert_eq!(candidate.text, text);
+            assert_eq!(candidate.offset, 5);
+
+            for (index, _) in text.char_indices() {
+                let offset = 5 + encode(encoding, &text[..index]).len() as u64;
+                assert_eq!(candidate.source_offset(index), offset, "{encoding}");
+                assert_eq!(candidate.source_end(index), offset, "{encoding}");
+                assert_eq!(candidate.text_index(offset), index, "{encoding}");
+            }
+            assert_eq!(
+                candidate.source_end(text.len()),
+                5 + encode(encoding, text).len() as u64
+            );
+        }
+    }
+
+    #[test]
+    fn encodings_merged_by_offset() {
+        let mut buffer = encode(Encoding::Utf16le, "Get-Process | Stop-Process");
+        buffer.extend([0; 3]);
+        buffer.extend(encode(Encoding::Utf8, "cat /etc/passwd"));
+        buffer.extend([0; 3]);
+
+        let candidates = strings(
+            &buffer,
+            Options::new(4).with_encodings(vec![Encoding::Utf8, Encoding::Utf16le]),
+        );
+        let found: Vec<(u64, Encoding, &str)> = candidates
+            .iter()
+            .map(|candidate| {
+                (
+                    candidate.offset,
+                    candidate.encoding,
+                    candidate.text.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (0, Encoding::Utf16le, "Get-Process | Stop-Process"),
+                (55, Encoding::Utf8, "cat /etc/passwd"),
+            ]
+        );
+    }
+
+    #[test]
+    fn long_string_split_in_overlapping_windows() {
+        let text: String = (0..100).map(|i| char::from(b'a' + i % 26)).collect();
+        let mut buffer = vec![0; 3];
+        buffer.extend(text.as_bytes());
+        buffer.push(0);
+
+        let windows = strings(&buffer, Options::new(4).with_max_length(40, 10));
+        let found: Vec<(u64, usize, bool, bool)> = windows
+            .iter()
+            .map(|window| {
+                (
+                    window.offset,
+                    window.text.len(),
+                    window.continued,
+                    window.truncated,
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                (3, 40, false, true),
+                (33, 40, true, true),
+                (63, 40, true, false),
+            ]
+        );
+        for window in &windows {
+            let start = window.offset as usize - 3;
+            assert_eq!(window.text, text[start..start + window.text.len()]);
+        }
+    }
+
+    #[test]
+    fn overlap_limited_to_half_a_window() {
+        let text = "x".repeat(50);
+        let windows = strings(text.as_bytes(), Options::new(4).with_max_length(20, 15));
+        let offsets: Vec<u64> = windows.iter().map(|window| window.offset).collect();
+        assert_eq!(offsets, [0, 10, 20, 30]);
+    }
+
+    /// Stream giving at most `chunk` bytes per read
+    struct Chunked<'a> {
+        buffer: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let length = buf.len().min(self.chunk).min(self.buffer.len());
+            buf[..length].copy_from_slice(&self.buffer[..length]);
+            self.buffer = &self.buffer[length..];
+            Ok(length)
+        }
+    }
+
+    #[test]
+    fn stream_yields_the_same_strings() {
+        // several blocks of strings in both encodings, and a string longer than a window
+        let mut buffer = vec![];
+        for i in 0..20000 {
+            buffer.extend(format!("string number {i}").as_bytes());
+            buffer.extend([0; 3]);
+            if i % 7 == 0 {
+                buffer.extend(encode(Encoding::Utf16le, &format!("wide string {i}")));
+                buffer.extend([0; 2]);
+            }
+        }
+        buffer.extend("y".repeat(5000).as_bytes());
+        buffer.push(0);
+        assert!(buffer.len() as u64 > 4 * BLOCK_SIZE);
+
+        let options = Options::new(8)
+            .with_max_length(1000, 100)
+            .with_encodings(vec![Encoding::Utf8, Encoding::Utf16le]);
+        let streamed: Vec<Candidate> = StreamStringsIterator::with_options(
+            Chunked {
+                buffer: &buffer,
+                chunk: 3000,
+            },
+            options.clone(),
+        )
+        .collect();
+        assert_eq!(streamed, strings(&buffer, options));
+    }
+
+    #[test]
+    fn runs_joined_across_short_gaps() {
+        let mut buffer = b"powershell -enc".to_vec();
+        buffer.extend([0; 3]);
+        buffer.extend(b"SQBFAFgA");
+        buffer.push(0);
+
+        // the gap is too wide
+        let candidates = strings(&buffer, Options::new(4).with_gap(2));
+        assert_eq!(candidates.len(), 2);
+
+        let candidates = strings(&buffer, Options::new(4).with_gap(3));
+        assert_eq!(candidates.len(), 1);
+        let candidate = &candidates[0];
+        assert_eq!(candidate.text, "powershell -encSQBFAFgA");
+        assert_eq!(candidate.gaps, [(15, 3)]);
+
+        // a char after the gap is found after the skipped bytes
+        assert_eq!(candidate.source_end(15), 15);
+        assert_eq!(candidate.source_offset(15), 18);
+        assert_eq!(candidate.source_offset(16), 19);
+        assert_eq!(candidate.text_index(18), 15);
+        assert_eq!(candidate.source_end(candidate.text.len()), 26);
+
+        // a range ends before the gap following it, and starts after the gap preceding it
+        let ranges = [(0, 10), (11, 15), (15, 23)];
+        let expected: Vec<(u64, u64)> = ranges
+            .iter()
+            .map(|(start, end)| (candidate.source_offset(*start), candidate.source_end(*end)))
+            .collect();
+        assert_eq!(candidate.source_ranges(&ranges), expected);
+        assert_eq!(expected, [(0, 10), (11, 15), (18, 26)]);
+    }
+
+    #[test]
+    fn ranges_yield_the_same_strings() {
+        // the second string of a record is found after a char skipped with its gap,
+        // which doesn't make it the continuation of a string cut by the range
+        let mut buffer = vec![];
+        let mut records = vec![];
+        for i in 0..100 {
+            records.push(buffer.len() as u64);
+            buffer.extend(format!("string number {i}").as_bytes());
+            buffer.extend(b"\0\0\0a\0");
+            buffer.extend(format!("second string {i}").as_bytes());
+            buffer.extend([0; 3]);
+            if i % 10 == 0 {
+                // several windows long
+                buffer.extend("x".repeat(100).as_bytes());
+                buffer.extend([0; 3]);
+            }
+        }
+        records.push(buffer.len() as u64);
+
+        for gap in [1, 2] {
+            let options = Options::new(4).with_gap(gap).with_max_length(40, 10);
+            let expected = strings(&buffer, options.clone());
+            assert_eq!(expected.len(), 200 + 10 * 3);
+            for size in [1, 2, 7] {
+                let ranges: Vec<Candidate> = (0..100)
+                    .step_by(size)
+                    .flat_map(|first| {
+                        let (start, end) = (records[first], records[(first + size).min(100)]);
+                        SliceStringsIterator::with_range(&buffer, options.clone(), start, end)
+                    })
+                    .collect();
+                assert_eq!(ranges, expected, "gap {gap}, ranges of {size} records");
+            }
+
+            // a range starting inside the second window of a long string
+            let long = records[1] - 103;
+            let ranges: Vec<Candidate> = [(0, long + 50), (long + 50, buffer.len() as u64)]
+                .into_iter()
+                .flat_map(|(start, end)| {
+                    SliceStringsIterator::with_range(&buffer, options.clone(), start, end)
+                })
+                .collect();
+            assert_eq!(ranges, expected, "gap {gap}, ranges in a long string");
+        }
     }
 }
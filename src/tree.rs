@@ -1,7 +1,6 @@
 use crate::error::Result;
 use crate::rule::Rule;
-use tree_sitter::{Node as TreeNode, Tree as TreeSitter};
-use tree_sitter_traversal2::{traverse, Order};
+use tree_sitter::{Node as TreeNode, Tree as TreeSitter, TreeCursor};
 
 /// A node view use to explore the tree
 /// without mutability
@@ -25,7 +24,10 @@ impl<'a> Node<'a> {
     }
 
     pub fn child(&self, index: usize) -> Option<Node<'a>> {
-        Some(Node::new(self.node.child(index)?, self.source))
+        Some(Node::new(
+            self.node.child(u32::try_from(index).ok()?)?,
+            self.source,
+        ))
     }
 
     pub fn named_child(&self, index: &str) -> Option<Node<'a>> {
@@ -76,6 +78,36 @@ impl<'a> Node<'a> {
         self.node.is_extra()
     }
 
+    /// The node or one of its descendants is an ERROR or MISSING node
+    pub fn has_error(&self) -> bool {
+        self.node.has_error()
+    }
+
+    /// Number of ERROR and MISSING nodes inside the node,
+    /// and number of bytes of the outermost ERROR nodes, see `Tolerance`
+    pub fn errors(&self) -> (usize, usize) {
+        if !self.node.has_error() {
+            return (0, 0);
+        }
+
+        let mut errors = 0;
+        let mut error_bytes = 0;
+        // end of the last outermost ERROR node, the pre-order gives the outermost first
+        let mut error_end = 0;
+        for node in PreOrder::new(self.node.walk()) {
+            if node.is_missing() {
+                errors += 1;
+            } else if node.is_error() {
+                errors += 1;
+                if node.start_byte() >= error_end {
+                    error_bytes += node.end_byte() - node.start_byte();
+                    error_end = node.end_byte();
+                }
+            }
+        }
+        (errors, error_bytes)
+    }
+
     pub fn child_count(&self) -> usize {
         self.node.child_count()
     }
@@ -106,7 +138,7 @@ impl<'a> Node<'a> {
         // Stack use to call 'leave' method when all children are handled
         let mut stack: Vec<(TreeNode, usize, bool)> = vec![];
 
-        for node in traverse(self.node.walk(), Order::Pre) {
+        for node in PreOrder::new(self.node.walk()) {
             stack.push((node, node.child_count(), is_visiting));
             if is_visiting {
                 is_visiting = is_visiting && rule.enter(&Node::new(node, self.source))?;
@@ -136,7 +168,7 @@ impl<'a> Node<'a> {
 
                 // decrement number of children handled
                 if let Some(l) = stack.last_mut() {
-                    l.1 = l.1 - 1;
+                    l.1 -= 1;
                 }
             }
         }
@@ -144,6 +176,45 @@ impl<'a> Node<'a> {
     }
 }
 
+/// Walk a node and all its descendants, parents first
+struct PreOrder<'a> {
+    cursor: TreeCursor<'a>,
+    done: bool,
+}
+
+impl<'a> PreOrder<'a> {
+    fn new(cursor: TreeCursor<'a>) -> Self {
+        Self {
+            cursor,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = TreeNode<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let node = self.cursor.node();
+
+        // go down first, then to the next sibling of the closest ancestor
+        if !self.cursor.goto_first_child() {
+            while !self.cursor.goto_next_sibling() {
+                if !self.cursor.goto_parent() {
+                    self.done = true;
+                    break;
+                }
+            }
+        }
+
+        Some(node)
+    }
+}
+
 pub struct NodeIterator<'a> {
     inner: Node<'a>,
     index: usize,
@@ -199,7 +270,125 @@ impl<'a> Tree<'a> {
         node.apply(rule)
     }
 
-    pub fn root(&self) -> Result<Node> {
+    pub fn root(&self) -> Result<Node<'_>> {
         Ok(Node::new(self.tree_sitter.root_node(), self.source))
     }
+
+    /// Number of ERROR and MISSING nodes inside each of the sorted regions
+    pub fn error_counts(&self, regions: &[(usize, usize)]) -> Vec<usize> {
+        // the pre-order gives the errors sorted by start
+        let errors: Vec<(usize, usize)> = PreOrder::new(self.tree_sitter.walk())
+            .filter(|node| node.is_error() || node.is_missing())
+            .map(|node| (node.start_byte(), node.end_byte()))
+            .collect();
+
+        regions
+            .iter()
+            .map(|(start, end)| {
+                let first = errors.partition_point(|error| error.0 < *start);
+                errors[first..]
+                    .iter()
+                    .take_while(|error| error.0 <= *end)
+                    .filter(|error| error.1 <= *end)
+                    .count()
+            })
+            .collect()
+    }
+
+    /// Merge the spans of the matched nodes into maximal regions
+    ///
+    /// Overlapping spans and spans separated by valid code only are merged,
+    /// an ERROR or MISSING node or a token of an ERROR node
+    /// between two spans splits them apart
+    pub fn regions(&self, spans: impl IntoIterator<Item = (usize, usize)>) -> Vec<(usize, usize)> {
+        let mut spans: Vec<(usize, usize)> = spans.into_iter().collect();
+        spans.sort_unstable();
+
+        // an ERROR node may wrap valid code, its own tokens are the junk
+        let junk: Vec<usize> = PreOrder::new(self.tree_sitter.walk())
+            .filter(|node| {
+                node.is_error()
+                    || node.is_missing()
+                    || (node.child_count() == 0 && node.parent().is_some_and(|p| p.is_error()))
+            })
+            .map(|node| node.start_byte())
+            .collect();
+
+        let mut regions: Vec<(usize, usize)> = vec![];
+        for (start, end) in spans {
+            match regions.last_mut() {
+                Some(region) if start <= region.1 || !Self::has_junk(&junk, region.1, start) => {
+                    region.1 = region.1.max(end)
+                }
+                _ => regions.push((start, end)),
+            }
+        }
+        regions
+    }
+
+    /// Some junk starts between two offsets, the junk offsets are sorted
+    fn has_junk(junk: &[usize], start: usize, end: usize) -> bool {
+        junk.get(junk.partition_point(|offset| *offset < start))
+            .is_some_and(|offset| *offset < end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Tolerance;
+    use crate::visitor::LanguageVisitor;
+
+    fn build_bash_tree(source: &str) -> Tree<'_> {
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&tree_sitter_bash::language()).unwrap();
+        Tree::new(source.as_bytes(), parser.parse(source, None).unwrap())
+    }
+
+    /// Spans of the loops and conditions accepted with a tolerance
+    fn spans(tree: &Tree, tolerance: Tolerance) -> Vec<(usize, usize)> {
+        let mut rule = LanguageVisitor::new(|c| matches!(c, "if_statement" | "for_statement"))
+            .with_tolerance(tolerance);
+        tree.apply(&mut rule).unwrap();
+        rule.spans.iter().collect()
+    }
+
+    #[test]
+    fn spans_joined_by_valid_code() {
+        let src = "if true; then echo a; fi\necho b\nfor i in 1; do echo $i; done";
+        let tree = build_bash_tree(src);
+        assert_eq!(tree.regions([(32, 60), (0, 24)]), [(0, 60)]);
+        assert_eq!(tree.regions([(0, 24), (5, 20)]), [(0, 24)]);
+        assert_eq!(tree.error_counts(&[(0, 60)]), [0]);
+    }
+
+    #[test]
+    fn spans_split_by_junk() {
+        let src = "if true; then echo a; fi\n)))\necho b; echo c";
+        let tree = build_bash_tree(src);
+        assert_eq!(spans(&tree, Tolerance::default()), [(0, 24)]);
+        assert_eq!(tree.regions([(0, 24), (29, 43)]), [(0, 24), (29, 43)]);
+        assert_eq!(tree.error_counts(&[(0, 24), (24, 43)]), [0, 1]);
+    }
+
+    #[test]
+    fn errors_tolerated_inside_a_node() {
+        let src = "for i in 1; do echo $i ))); done";
+        let tree = build_bash_tree(src);
+        assert_eq!(spans(&tree, Tolerance::default()), []);
+        assert_eq!(spans(&tree, Tolerance::new(1, 0.5)), [(0, 32)]);
+        assert_eq!(tree.error_counts(&[(0, 32)]), [1]);
+        // the error is too large a share of the loop
+        assert_eq!(spans(&tree, Tolerance::new(1, 0.01)), []);
+    }
+
+    #[test]
+    fn tolerance_bounds() {
+        let tolerance = Tolerance::new(2, 0.25);
+        assert!(tolerance.accepts(2, 10, 40));
+        assert!(!tolerance.accepts(3, 1, 40));
+        assert!(!tolerance.accepts(1, 11, 40));
+        assert!(Tolerance::default().accepts(0, 0, 40));
+        assert!(!Tolerance::default().accepts(1, 0, 40));
+    }
 }
@@ -0,0 +1,86 @@
+use crate::error::Result;
+use crate::parser::{detected_regions, Parser, Region, Tolerance};
+use crate::tree::Tree;
+use crate::visitor::LanguageVisitor;
+use tree_sitter_typescript::{
+    LANGUAGE_TSX as tsx_language, LANGUAGE_TYPESCRIPT as typescript_language,
+};
+
+fn build_typescript_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&typescript_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+fn build_tsx_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&tsx_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+fn detect(tree: &Tree, src: &str, tolerance: Tolerance) -> Result<Option<Vec<Region>>> {
+    let mut detection_rule = LanguageVisitor::new(|c| {
+        matches!(
+            c,
+            "function_declaration"
+                | "export_statement"
+                | "debugger_statement"
+                | "if_statement"
+                | "switch_statement"
+                | "for_statement"
+                | "for_in_statement"
+                | "while_statement"
+                | "do_statement"
+                | "try_statement"
+                | "with_statement"
+                // typescript only
+                | "interface_declaration"
+                | "type_alias_declaration"
+                | "enum_declaration"
+                | "abstract_class_declaration"
+        )
+    })
+    .with_tolerance(tolerance);
+
+    tree.apply(&mut detection_rule)?;
+
+    if detection_rule.is_matched {
+        let regions = tree.regions(detection_rule.spans.iter());
+        Ok(Some(detected_regions(
+            tree,
+            src,
+            &regions,
+            detection_rule.matches.iter(),
+        )))
+    } else {
+        Ok(None)
+    }
+}
+
+/// TypeScript parser
+///
+/// Plain TypeScript is tried first, then TSX,
+/// as type assertions in angle brackets are not valid TSX
+#[derive(Default)]
+pub struct Typescript {
+    tolerance: Tolerance,
+}
+
+impl Parser for Typescript {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        Ok(
+            match detect(&build_typescript_tree(src)?, src, self.tolerance)? {
+                Some(regions) => regions,
+                None => detect(&build_tsx_tree(src)?, src, self.tolerance)?.unwrap_or_default(),
+            },
+        )
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
@@ -1,13 +1,69 @@
 use crate::error::Result;
+use crate::parser::Tolerance;
 use crate::rule::Rule;
 use crate::tree::Node;
-use std::cmp::{max, min};
+
+/// Parsing errors found inside a discriminating node not left yet
+struct Frame {
+    start: usize,
+    errors: usize,
+    error_bytes: usize,
+}
+
+impl Frame {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            errors: 0,
+            error_bytes: 0,
+        }
+    }
+}
+
+/// Ranges of the nodes accepted by a rule, see `Tree::regions`
+#[derive(Default)]
+pub struct Spans(Vec<(usize, usize)>);
+
+impl Spans {
+    /// Add the range of a node left by a rule,
+    /// it replaces the ranges of the children left before it
+    pub fn push(&mut self, start: usize, end: usize) {
+        while self.0.last().is_some_and(|(last, _)| *last >= start) {
+            self.0.pop();
+        }
+        self.0.push((start, end));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+/// Start and kind of the discriminating nodes accepted by a rule,
+/// and whether parsing errors were tolerated inside them, see `Evidence`
+#[derive(Default)]
+pub struct Matches(Vec<(usize, &'static str, bool)>);
+
+impl Matches {
+    pub fn push(&mut self, node: &Node, partial: bool) {
+        self.0.push((node.start_abs(), node.kind(), partial));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &'static str, bool)> + '_ {
+        self.0.iter().copied()
+    }
+}
 
 pub struct LanguageVisitor {
     pub is_matched: bool,
-    stack: Vec<bool>,
-    pub start: Option<usize>,
-    pub end: Option<usize>,
+    stack: Vec<Frame>,
+    /// Matched nodes and the valid nodes extending them
+    pub spans: Spans,
+    /// Number of ERROR nodes around the current node
+    error_depth: usize,
+    /// Discriminating nodes matched
+    pub matches: Matches,
+    tolerance: Tolerance,
     match_fn: fn(&str) -> bool,
 }
 
@@ -15,60 +71,78 @@ impl LanguageVisitor {
     pub fn new(match_fn: fn(&str) -> bool) -> Self {
         Self {
             is_matched: false,
-            stack: vec![true],
-            start: None,
-            end: None,
+            stack: vec![Frame::new(0)],
+            spans: Spans::default(),
+            error_depth: 0,
+            matches: Matches::default(),
+            tolerance: Tolerance::default(),
             match_fn,
         }
     }
+
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// The errors of the frame are tolerated up to the end of the node
+    fn accepts(&self, frame: &Frame, node: &Node) -> bool {
+        self.tolerance.accepts(
+            frame.errors,
+            frame.error_bytes,
+            node.end_abs() - frame.start,
+        )
+    }
 }
 
 impl<'a> Rule<'a> for LanguageVisitor {
     fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
-        if node.child_count() > 1 {
-            if (self.match_fn)(&node.kind()) {
-                self.stack.push(true);
-            }
+        if node.kind() == "ERROR" {
+            self.error_depth += 1;
+        }
+        if node.child_count() > 1 && (self.match_fn)(node.kind()) {
+            self.stack.push(Frame::new(node.start_abs()));
         }
         Ok(true)
     }
 
     fn leave(&mut self, node: &Node<'a>) -> Result<()> {
-        // invalidate the stack
-        if node.kind() == "ERROR" || node.text()? == "" {
-            for c in self.stack.iter_mut() {
-                *c = false;
+        if node.kind() == "ERROR" {
+            self.error_depth -= 1;
+        }
+
+        // empty node is a MISSING node => parsing error
+        if node.kind() == "ERROR" || node.text()?.is_empty() {
+            // the bytes of nested ERROR nodes are counted with the outermost one
+            let error_bytes = if self.error_depth == 0 {
+                node.end_abs() - node.start_abs()
+            } else {
+                0
+            };
+            for frame in self.stack.iter_mut() {
+                frame.errors += 1;
+                frame.error_bytes += error_bytes;
             }
         }
 
-        if node.child_count() > 1 {
-            if (self.match_fn)(&node.kind()) {
-                if self.stack.pop().unwrap_or(false) {
-                    self.start = Some(min(
-                        self.start.unwrap_or(node.start_abs()),
-                        node.start_abs(),
-                    ));
-                    self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
+        if node.child_count() > 1 && (self.match_fn)(node.kind()) {
+            if let Some(frame) = self.stack.pop() {
+                if self.accepts(&frame, node) {
+                    self.spans.push(node.start_abs(), node.end_abs());
                     self.is_matched = true;
+                    self.matches.push(node, frame.errors > 0);
                 }
             }
         }
 
-        // empty node is a MISSING node => parsing error
-        if node.text()? == "" {
-            for c in self.stack.iter_mut() {
-                *c = false;
+        // the children of an ERROR node are left before it is counted,
+        // they are junk and never extend the span
+        if let Some(frame) = self.stack.last() {
+            if self.is_matched && self.accepts(frame, node) && self.error_depth == 0 {
+                self.spans.push(node.start_abs(), node.end_abs());
             }
         }
 
-        if self.is_matched && self.stack.last() == Some(&true) {
-            self.start = Some(min(
-                self.start.unwrap_or(node.start_abs()),
-                node.start_abs(),
-            ));
-            self.end = Some(max(self.end.unwrap_or(node.end_abs()), node.end_abs()));
-        }
-
         Ok(())
     }
 }
@@ -0,0 +1,207 @@
+use crate::csharp::Csharp;
+use crate::error::Result;
+use crate::js::Javascript;
+use crate::parser::{embedded_regions, Parser, Region, Tolerance};
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use tree_sitter_xml::LANGUAGE_XML as xml_language;
+
+fn build_xml_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&xml_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+/// Code block embedded in an XML document
+struct CodeBlock {
+    /// Offset of the code in the source
+    start: usize,
+    /// Offset of the byte following the code in the source
+    end: usize,
+    /// Value of the language attribute, lower case
+    language: String,
+    /// Kind of document the block comes from
+    container: &'static str,
+}
+
+/// Collect the msxsl:script elements of XSL stylesheets
+/// and the Code elements of MSBuild inline tasks
+#[derive(Default)]
+struct CodeCollector {
+    blocks: Vec<CodeBlock>,
+}
+
+impl CodeCollector {
+    /// Value of an attribute of a start tag, lower case
+    fn attribute(start_tag: &Node, name: &str) -> Result<Option<String>> {
+        for attribute in start_tag.iter().filter(|c| c.kind() == "Attribute") {
+            let (Some(key), Some(value)) = (attribute.child(0), attribute.iter().last()) else {
+                continue;
+            };
+            if key.text()?.eq_ignore_ascii_case(name) {
+                return Ok(Some(value.text()?.trim_matches(['"', '\'']).to_lowercase()));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Range of the code held by the element, in a CDATA section or as plain text
+    fn code(element: &Node) -> Result<Option<(usize, usize)>> {
+        let Some(content) = element.iter().find(|c| c.kind() == "content") else {
+            return Ok(None);
+        };
+
+        let node = match content
+            .iter()
+            .find(|c| c.kind() == "CDSect")
+            .and_then(|c| c.iter().find(|c| c.kind() == "CData"))
+        {
+            Some(cdata) => cdata,
+            None => content,
+        };
+
+        let text = node.text()?;
+        let start = node.start_abs() + text.len() - text.trim_start().len();
+        Ok(Some((start, start + text.trim().len())))
+    }
+}
+
+impl<'a> Rule<'a> for CodeCollector {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        if node.kind() != "element" {
+            return Ok(true);
+        }
+
+        let Some(start_tag) = node.iter().find(|c| c.kind() == "STag") else {
+            return Ok(true);
+        };
+        let Some(name) = start_tag.iter().find(|c| c.kind() == "Name") else {
+            return Ok(true);
+        };
+
+        // msxsl is the usual prefix but any prefix may be bound to the namespace
+        let name = name.text()?.to_lowercase();
+        let (container, default_language) = match name.rsplit(':').next() {
+            Some("script") if name.contains(':') => ("xslt", "jscript"),
+            Some("code") if name == "code" => ("msbuild", "cs"),
+            _ => return Ok(true),
+        };
+
+        if let Some((start, end)) = Self::code(node)? {
+            self.blocks.push(CodeBlock {
+                start,
+                end,
+                language: Self::attribute(&start_tag, "language")?
+                    .unwrap_or_else(|| String::from(default_language)),
+                container,
+            });
+        }
+
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Scripts embedded in XSL stylesheets and MSBuild project files
+///
+/// The content of the msxsl:script and Code elements is given to the parser
+/// of its language, each region found in a block is a hit of its own.
+/// The variant reports the container, xslt or msbuild
+#[derive(Default)]
+pub struct Xml {
+    tolerance: Tolerance,
+}
+
+impl Parser for Xml {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_xml_tree(src)?;
+        let mut collector = CodeCollector::default();
+        tree.apply(&mut collector)?;
+
+        let mut regions = vec![];
+
+        for block in collector.blocks {
+            let mut parser: Box<dyn Parser> = match block.language.as_str() {
+                "jscript" | "javascript" | "ecmascript" => Box::<Javascript>::default(),
+                "c#" | "cs" | "csharp" => Box::<Csharp>::default(),
+                // VBScript and VB.NET have no parser yet, skipped as documented in the --language help
+                _ => continue,
+            };
+            parser.set_tolerance(self.tolerance);
+
+            let found = parser.parse(&src[block.start..block.end])?;
+            regions.extend(
+                embedded_regions(src, found, move |index| block.start + index)
+                    .map(|region| region.with_variant(block.container)),
+            );
+        }
+
+        Ok(regions)
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn regions(src: &str) -> Vec<(usize, String, Option<String>)> {
+        Xml::default()
+            .parse(src)
+            .unwrap()
+            .into_iter()
+            .map(|region| (region.offset as usize, region.text, region.variant))
+            .collect()
+    }
+
+    #[test]
+    fn xslt_script_offsets() {
+        let code = "function run(cmd) { var shell = new ActiveXObject(\"WScript.Shell\"); return shell.Run(cmd); }";
+        let header = "<xsl:stylesheet version=\"1.0\" xmlns:msxsl=\"urn:schemas-microsoft-com:xslt\">\n<msxsl:script language=\"JScript\" implements-prefix=\"user\">\n";
+        let footer = "\n</msxsl:script>\n</xsl:stylesheet>\n";
+
+        // in a CDATA section
+        let src = format!("{header}<![CDATA[\n{code}\n]]>{footer}");
+        assert_eq!(
+            regions(&src),
+            vec![(
+                src.find(code).unwrap(),
+                code.to_string(),
+                Some("xslt".to_string())
+            )]
+        );
+
+        // as plain text
+        let src = format!("{header}{code}{footer}");
+        assert_eq!(
+            regions(&src),
+            vec![(
+                src.find(code).unwrap(),
+                code.to_string(),
+                Some("xslt".to_string())
+            )]
+        );
+    }
+
+    #[test]
+    fn msbuild_code_offset() {
+        let code = "public class T : Task { public override bool Execute() { Process.Start(\"calc.exe\"); return true; } }";
+        let src = format!("<Project>\n<UsingTask TaskName=\"T\" TaskFactory=\"CodeTaskFactory\">\n<Task>\n<Code Type=\"Class\" Language=\"cs\"><![CDATA[\n  {code}\n]]></Code>\n</Task>\n</UsingTask>\n</Project>\n");
+        assert_eq!(
+            regions(&src),
+            vec![(
+                src.find(code).unwrap(),
+                code.to_string(),
+                Some("msbuild".to_string())
+            )]
+        );
+    }
+}
@@ -0,0 +1,327 @@
+use crate::bash::Bash;
+use crate::error::Result;
+use crate::parser::{embedded_regions, Parser, Region, Tolerance};
+use crate::ps::Powershell;
+use crate::python::Python;
+use crate::rule::Rule;
+use crate::tree::{Node, Tree};
+use std::cmp::min;
+use tree_sitter_yaml::LANGUAGE as yaml_language;
+
+fn build_yaml_tree(source: &str) -> Result<Tree<'_>> {
+    let mut parser = tree_sitter::Parser::new();
+    parser.set_language(&yaml_language.into())?;
+
+    let tree_sitter = parser.parse(source, None).unwrap();
+    Ok(Tree::new(source.as_bytes(), tree_sitter))
+}
+
+/// Text of a key without its quotes
+fn key_text(pair: &Node) -> Result<Option<String>> {
+    Ok(match pair.named_child("key") {
+        Some(key) => Some(key.text()?.trim_matches(['"', '\'']).to_string()),
+        None => None,
+    })
+}
+
+/// Part of the key path brought by a node, a key or an index in a sequence
+fn path_segment(node: &Node) -> Result<Option<String>> {
+    Ok(match node.kind() {
+        "block_mapping_pair" | "flow_pair" => key_text(node)?.map(|key| format!(".{}", key)),
+        "block_sequence_item" | "flow_node" => match node.parent() {
+            Some(parent) if matches!(parent.kind(), "block_sequence" | "flow_sequence") => {
+                Some(format!(
+                    "[{}]",
+                    parent
+                        .iter()
+                        .filter(|c| c.kind() == node.kind())
+                        .position(|c| c == *node)
+                        .unwrap_or(0)
+                ))
+            }
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// Location of a node in the document, as `jobs.build.steps[1].run`
+fn key_path(node: &Node) -> Result<String> {
+    let mut path = vec![];
+    path.extend(path_segment(node)?);
+
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        path.extend(path_segment(&ancestor)?);
+        current = ancestor.parent();
+    }
+
+    path.reverse();
+    Ok(path.concat().trim_start_matches('.').to_string())
+}
+
+/// Script held by a scalar, without the indentation of the document
+struct Script {
+    code: String,
+    /// Offset of each line in the code and in the source
+    lines: Vec<(usize, usize)>,
+    /// Parser to use, bash, powershell or python
+    language: &'static str,
+    /// Key path of the scalar
+    path: String,
+}
+
+impl Script {
+    fn new(node: &Node, language: &'static str) -> Result<Self> {
+        let text = node.text()?;
+        let (body, offset, is_block) = match node.kind() {
+            // the header holds the | or > indicator
+            "block_scalar" => {
+                let header = text.find('\n').map(|i| i + 1).unwrap_or(text.len());
+                (&text[header..], node.start_abs() + header, true)
+            }
+            "double_quote_scalar" | "single_quote_scalar" => (
+                text.get(1..text.len() - 1).unwrap_or_default(),
+                node.start_abs() + 1,
+                false,
+            ),
+            _ => (text, node.start_abs(), false),
+        };
+
+        // the first line of a flow scalar follows the key, it is not indented
+        let indent = body
+            .lines()
+            .skip(usize::from(!is_block))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start_matches(' ').len())
+            .min()
+            .unwrap_or(0);
+
+        let mut code = String::new();
+        let mut lines = vec![];
+        let mut position = offset;
+        for (index, line) in body.split_inclusive('\n').enumerate() {
+            let strip = if index == 0 && !is_block {
+                0
+            } else {
+                min(indent, line.len() - line.trim_start_matches(' ').len())
+            };
+            lines.push((code.len(), position + strip));
+            code.push_str(&line[strip..]);
+            position += line.len();
+        }
+
+        Ok(Self {
+            code,
+            lines,
+            language,
+            path: key_path(node)?,
+        })
+    }
+
+    /// Offset in the source of a byte of the code
+    fn source_offset(&self, index: usize) -> usize {
+        let (code, source) = self
+            .lines
+            .iter()
+            .rev()
+            .find(|(code, _)| *code <= index)
+            .copied()
+            .unwrap_or_default();
+        source + index - code
+    }
+}
+
+/// Collect the values of the keys known to hold scripts
+#[derive(Default)]
+struct ScriptCollector {
+    scripts: Vec<Script>,
+}
+
+impl ScriptCollector {
+    /// Shell of a github actions step, set by the shell key next to run
+    fn step_shell(pair: &Node) -> Result<&'static str> {
+        let Some(mapping) = pair.parent() else {
+            return Ok("bash");
+        };
+
+        for sibling in mapping.iter() {
+            if key_text(&sibling)?.as_deref() != Some("shell") {
+                continue;
+            }
+            if let Some(value) = sibling.named_child("value") {
+                return Ok(
+                    match value.text()?.split_whitespace().next().unwrap_or_default() {
+                        "pwsh" | "powershell" => "powershell",
+                        "python" => "python",
+                        _ => "bash",
+                    },
+                );
+            }
+        }
+
+        Ok("bash")
+    }
+
+    /// Add the scalars of a value, a single script or a list of them
+    fn collect(&mut self, node: &Node, language: &'static str) -> Result<()> {
+        match node.kind() {
+            "block_scalar" | "plain_scalar" | "double_quote_scalar" | "single_quote_scalar" => {
+                self.scripts.push(Script::new(node, language)?)
+            }
+            "block_node"
+            | "flow_node"
+            | "block_sequence"
+            | "block_sequence_item"
+            | "flow_sequence" => {
+                for child in node.iter() {
+                    self.collect(&child, language)?;
+                }
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Rule<'a> for ScriptCollector {
+    fn enter(&mut self, node: &Node<'a>) -> Result<bool> {
+        if !matches!(node.kind(), "block_mapping_pair" | "flow_pair") {
+            return Ok(true);
+        }
+
+        let (Some(key), Some(value)) = (key_text(node)?, node.named_child("value")) else {
+            return Ok(true);
+        };
+
+        let language = match key.to_lowercase().as_str() {
+            // github actions
+            "run" => Self::step_shell(node)?,
+            // gitlab ci, azure pipelines and ansible tasks
+            "script"
+            | "before_script"
+            | "after_script"
+            | "bash"
+            | "shell"
+            | "command"
+            | "ansible.builtin.shell"
+            | "ansible.builtin.command" => "bash",
+            "powershell" | "pwsh" | "win_shell" | "ansible.windows.win_shell" => "powershell",
+            _ => return Ok(true),
+        };
+
+        self.collect(&value, language)?;
+        Ok(true)
+    }
+
+    fn leave(&mut self, _node: &Node<'a>) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Shell steps of CI/CD pipelines and infrastructure YAML
+///
+/// The values of the keys known to hold scripts, as the run steps of
+/// github actions, the script lists of gitlab ci or the shell tasks of ansible,
+/// are given to the bash, powershell or python parser.
+/// Each region found in a script is a hit of its own
+/// and the variant reports the key path of its script
+#[derive(Default)]
+pub struct Yaml {
+    tolerance: Tolerance,
+}
+
+impl Parser for Yaml {
+    fn parse(&mut self, src: &str) -> Result<Vec<Region>> {
+        let tree = build_yaml_tree(src)?;
+        let mut collector = ScriptCollector::default();
+        tree.apply(&mut collector)?;
+
+        let mut regions = vec![];
+
+        for script in collector.scripts {
+            let mut parser: Box<dyn Parser> = match script.language {
+                "powershell" => Box::<Powershell>::default(),
+                "python" => Box::<Python>::default(),
+                _ => Box::<Bash>::default(),
+            };
+            parser.set_tolerance(self.tolerance);
+
+            let found = parser.parse(&script.code)?;
+            // the indentation removed from the code is back in the source
+            regions.extend(
+                embedded_regions(src, found, |index| script.source_offset(index))
+                    .map(|region| region.with_variant(script.path.clone())),
+            );
+        }
+
+        Ok(regions)
+    }
+
+    fn set_tolerance(&mut self, tolerance: Tolerance) {
+        self.tolerance = tolerance;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scripts(src: &str) -> Vec<Script> {
+        let tree = build_yaml_tree(src).unwrap();
+        let mut collector = ScriptCollector::default();
+        tree.apply(&mut collector).unwrap();
+        collector.scripts
+    }
+
+    #[test]
+    fn block_scalar_skips_header_and_indentation() {
+        let src = "jobs:\n  build:\n    steps:\n      - uses: actions/checkout@v4\n      - run: |\n          if [ -f x ]; then\n            rm x\n          fi\n";
+        let scripts = scripts(src);
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].path, "jobs.build.steps[1].run");
+        assert_eq!(scripts[0].code, "if [ -f x ]; then\n  rm x\nfi\n");
+        assert_eq!(scripts[0].source_offset(0), src.find("if [").unwrap());
+        let rm = scripts[0].code.find("rm").unwrap();
+        assert_eq!(scripts[0].source_offset(rm), src.find("rm x").unwrap());
+
+        let regions = Yaml::default().parse(src).unwrap();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].offset, src.find("if [").unwrap() as u64);
+        assert!(regions[0].text.trim_end().ends_with("fi"));
+        assert_eq!(
+            regions[0].variant,
+            Some("jobs.build.steps[1].run".to_string())
+        );
+    }
+
+    #[test]
+    fn quoted_scalar_strips_quotes_and_keeps_first_line() {
+        let src = "- name: clean\n  shell: \"for f in *.o; do\n    rm $f; done\"\n";
+        let scripts = scripts(src);
+        assert_eq!(scripts.len(), 1);
+        assert_eq!(scripts[0].path, "[0].shell");
+        assert_eq!(scripts[0].code, "for f in *.o; do\nrm $f; done");
+        assert_eq!(scripts[0].source_offset(0), src.find("for").unwrap());
+        let rm = scripts[0].code.find("rm").unwrap();
+        assert_eq!(scripts[0].source_offset(rm), src.find("rm").unwrap());
+
+        let regions = Yaml::default().parse(src).unwrap();
+        assert_eq!(regions.len(), 1);
+        assert_eq!(regions[0].offset, src.find("for").unwrap() as u64);
+        assert_eq!(regions[0].text, "for f in *.o; do\n    rm $f; done");
+    }
+
+    #[test]
+    fn script_list_yields_one_region_per_item() {
+        let src = "build:\n  script:\n    - if [ -d build ]; then rm -rf build; fi\n    - 'for f in *.o; do rm $f; done'\n";
+        let regions = Yaml::default().parse(src).unwrap();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].offset, src.find("if").unwrap() as u64);
+        assert_eq!(regions[0].text, "if [ -d build ]; then rm -rf build; fi");
+        assert_eq!(regions[0].variant, Some("build.script[0]".to_string()));
+        assert_eq!(regions[1].offset, src.find("for").unwrap() as u64);
+        assert_eq!(regions[1].text, "for f in *.o; do rm $f; done");
+        assert_eq!(regions[1].variant, Some("build.script[1]".to_string()));
+    }
+}